@@ -1,4 +1,7 @@
-use crate::{scanner::Token, values::LoxObj};
+use crate::{
+    scanner::{Span, Token},
+    values::LoxObj,
+};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Expr {
@@ -20,12 +23,49 @@ pub enum Expr {
         object: Box<Expr>,
         name: Token,
     },
+    /// `opening` and `closing` are its parentheses.
     Grouping {
+        opening: Token,
         expression: Box<Expr>,
+        closing: Token,
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    IndexSet {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    /// `opening` and `closing` are its brackets.
+    List {
+        opening: Token,
+        elements: Vec<Expr>,
+        closing: Token,
+    },
+    /// A string literal with `${}` expressions. The literal text between
+    /// them is kept as string `Literal`s.
+    Interpolation {
+        parts: Vec<Expr>,
     },
     Literal {
+        token: Token,
         value: LoxObj,
     },
+    Lambda {
+        keyword: Token,
+        params: Vec<Token>,
+        body: Vec<Stmt>,
+    },
+    /// `opening` and `closing` are its braces.
+    Map {
+        opening: Token,
+        entries: Vec<(Expr, Expr)>,
+        closing: Token,
+    },
     Logical {
         left: Box<Expr>,
         operator: Token,
@@ -52,50 +92,196 @@ pub enum Expr {
     },
 }
 
+impl Expr {
+    /// The source region covered by this expression. Synthesized nodes, like
+    /// the `nil` of a bare `return;`, use the span of the token that caused them.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Assignment { name, value } => name.span.to(&value.span()),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                left.span().to(&right.span())
+            }
+            Expr::Call { callee, paren, .. } => callee.span().to(&paren.span),
+            Expr::Get { object, name } => object.span().to(&name.span),
+            Expr::Grouping {
+                opening, closing, ..
+            }
+            | Expr::List {
+                opening, closing, ..
+            }
+            | Expr::Map {
+                opening, closing, ..
+            } => opening.span.to(&closing.span),
+            Expr::Index {
+                object, bracket, ..
+            } => object.span().to(&bracket.span),
+            Expr::IndexSet { object, value, .. } | Expr::Set { object, value, .. } => {
+                object.span().to(&value.span())
+            }
+            Expr::Lambda { keyword, body, .. } => match body.last() {
+                Some(stmt) => keyword.span.to(&stmt.span()),
+                None => keyword.span.clone(),
+            },
+            Expr::Interpolation { parts } => match (parts.first(), parts.last()) {
+                (Some(first), Some(last)) => first.span().to(&last.span()),
+                _ => Span::default(),
+            },
+            Expr::Literal { token, .. } => token.span.clone(),
+            Expr::Super { keyword, method } => keyword.span.to(&method.span),
+            Expr::This { keyword } => keyword.span.clone(),
+            Expr::Unary { operator, right } => operator.span.to(&right.span()),
+            Expr::Variable { name } => name.span.clone(),
+        }
+    }
+}
+
 pub trait ExprVisitor<T> {
     fn visit_expr(&mut self, expression: &Expr) -> T;
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Stmt {
     Block {
+        brace: Token,
         statements: Vec<Stmt>,
     },
+    Break {
+        keyword: Token,
+    },
     Class {
+        keyword: Token,
         name: Token,
         methods: Vec<Stmt>,
         superclass: Option<Box<Expr>>,
     },
+    Continue {
+        keyword: Token,
+    },
     Expression {
         expression: Box<Expr>,
     },
+    /// `from "path" import a, b;`. The keyword is the `from` identifier.
+    FromImport {
+        keyword: Token,
+        path: Token,
+        names: Vec<Token>,
+    },
+    /// The keyword is `fun`, or the name for methods.
     Function {
+        keyword: Token,
         name: Token,
         params: Vec<Token>,
         body: Vec<Stmt>,
     },
     If {
+        keyword: Token,
         condition: Box<Expr>,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
+    /// `import "path" as name;`
+    Import {
+        keyword: Token,
+        path: Token,
+        name: Token,
+    },
     Print {
+        keyword: Token,
         expression: Box<Expr>,
     },
     Return {
         keyword: Token,
         expression: Box<Expr>,
     },
+    Throw {
+        keyword: Token,
+        expression: Box<Expr>,
+    },
+    Try {
+        keyword: Token,
+        body: Vec<Stmt>,
+        catch: Option<(Token, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>,
+    },
     Var {
+        keyword: Token,
         name: Token,
         initializer: Box<Expr>,
     },
+    /// The keyword is `for` for loops desugared from one.
     While {
+        keyword: Token,
         condition: Box<Expr>,
         body: Box<Stmt>,
+        increment: Option<Box<Expr>>,
     },
 }
 
+impl Stmt {
+    /// The source region covered by this statement.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block { brace, statements } => match statements.last() {
+                Some(last) => brace.span.to(&last.span()),
+                None => brace.span.clone(),
+            },
+            Stmt::Break { keyword } | Stmt::Continue { keyword } => keyword.span.clone(),
+            Stmt::Class {
+                keyword,
+                name,
+                methods,
+                ..
+            } => match methods.last() {
+                Some(last) => keyword.span.to(&last.span()),
+                None => keyword.span.to(&name.span),
+            },
+            Stmt::Function {
+                keyword,
+                name,
+                body,
+                ..
+            } => match body.last() {
+                Some(last) => keyword.span.to(&last.span()),
+                None => keyword.span.to(&name.span),
+            },
+            Stmt::Expression { expression } => expression.span(),
+            Stmt::If {
+                keyword,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let last = else_branch.as_ref().unwrap_or(then_branch);
+                keyword.span.to(&last.span())
+            }
+            Stmt::Return {
+                keyword,
+                expression,
+            }
+            | Stmt::Throw {
+                keyword,
+                expression,
+            }
+            | Stmt::Print {
+                keyword,
+                expression,
+            } => keyword.span.to(&expression.span()),
+            Stmt::Try { keyword, .. } => keyword.span.clone(),
+            Stmt::FromImport { keyword, names, .. } => match names.last() {
+                Some(last) => keyword.span.to(&last.span),
+                None => keyword.span.clone(),
+            },
+            Stmt::Import { keyword, name, .. } => keyword.span.to(&name.span),
+            Stmt::Var {
+                keyword,
+                initializer,
+                ..
+            } => keyword.span.to(&initializer.span()),
+            Stmt::While { keyword, body, .. } => keyword.span.to(&body.span()),
+        }
+    }
+}
+
 pub trait StmtVisitor<T> {
     fn visit_stmt(&mut self, statement: &Stmt) -> T;
 }
@@ -29,11 +29,61 @@ impl ExprVisitor<String> for AstPrinter {
                 operator,
                 right,
             } => self.parenthesize(&operator.lexeme, &[left, right]),
-            Expr::Grouping { expression } => self.parenthesize("group", &[expression]),
-            Expr::Literal { value } => format!("{value}"),
+            Expr::Grouping { expression, .. } => self.parenthesize("group", &[expression]),
+            Expr::Literal { value, .. } => format!("{value}"),
             Expr::Unary { operator, right } => self.parenthesize(&operator.lexeme, &[right]),
             Expr::Variable { name } => format!("(var {})", name.lexeme),
-            _ => todo!(),
+            Expr::Assignment { name, value } => {
+                self.parenthesize(format!("= {}", name.lexeme), &[value])
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => self.parenthesize(&operator.lexeme, &[left, right]),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                let mut exprs = vec![callee.as_ref()];
+                exprs.extend(arguments.iter());
+                self.parenthesize("call", &exprs)
+            }
+            Expr::Get { object, name } => {
+                self.parenthesize(format!(". {}", name.lexeme), &[object])
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => self.parenthesize(format!("= .{}", name.lexeme), &[object, value]),
+            Expr::Index { object, index, .. } => self.parenthesize("[]", &[object, index]),
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => self.parenthesize("= []", &[object, index, value]),
+            Expr::Interpolation { parts } => {
+                let parts: Vec<_> = parts.iter().collect();
+                self.parenthesize("interpolate", &parts)
+            }
+            Expr::List { elements, .. } => {
+                let elements: Vec<_> = elements.iter().collect();
+                self.parenthesize("list", &elements)
+            }
+            Expr::Map { entries, .. } => {
+                let exprs: Vec<_> = entries
+                    .iter()
+                    .flat_map(|(key, value)| [key, value])
+                    .collect();
+                self.parenthesize("map", &exprs)
+            }
+            Expr::Lambda { params, .. } => {
+                let params: Vec<_> = params.iter().map(|param| param.lexeme.as_str()).collect();
+                format!("(fun ({}))", params.join(" "))
+            }
+            Expr::Super { method, .. } => format!("(super {})", method.lexeme),
+            Expr::This { .. } => String::from("this"),
         }
     }
 }
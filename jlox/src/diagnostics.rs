@@ -0,0 +1,388 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    io::{self, IsTerminal},
+};
+
+use crate::{
+    errors::{collapse_frames, Errors, Frame, LoxError, RuntimeError},
+    scanner::Span,
+    warnings::Warning,
+};
+
+/// The source text of every file that was run, by the id its spans use.
+pub type SourceMap = HashMap<u32, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// The stage of running a program that reported a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Scan,
+    Parse,
+    Resolve,
+    Runtime,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Scan => "scan",
+            Phase::Parse => "parse",
+            Phase::Resolve => "resolve",
+            Phase::Runtime => "runtime",
+        }
+    }
+}
+
+/// How a [`Renderer`] writes diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Source snippets with carets, meant to be read by people.
+    #[default]
+    Human,
+    /// One JSON object per line, meant to be read by tools.
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn from_name(name: &str) -> Option<ErrorFormat> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A secondary location attached to a diagnostic, e.g. where a variable was
+/// first declared.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub phase: Phase,
+    /// The name of the warning kind, for diagnostics that can be allowed.
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    /// The call stack of a runtime error, innermost call first.
+    pub trace: Vec<Frame>,
+}
+
+impl Diagnostic {
+    pub fn from_error(error: &LoxError, phase: Phase) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            phase,
+            code: None,
+            message: error.message.clone(),
+            span: error.span.clone(),
+            labels: error.labels.clone(),
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            phase: Phase::Runtime,
+            code: None,
+            message: error.message.clone(),
+            span: error.span.clone(),
+            labels: Vec::new(),
+            trace: error.trace.clone(),
+        }
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(warning: &Warning) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            phase: Phase::Resolve,
+            code: Some(warning.kind.name()),
+            message: warning.message.clone(),
+            span: warning.span.clone(),
+            labels: warning.labels.clone(),
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl Errors {
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Errors::Scanning(errors) => Self::static_diagnostics(errors, Phase::Scan),
+            Errors::Parsing(errors) => Self::static_diagnostics(errors, Phase::Parse),
+            Errors::Resolving(errors) => Self::static_diagnostics(errors, Phase::Resolve),
+            Errors::Runtime(error) => {
+                let mut diagnostics = vec![Diagnostic::from(error)];
+                if let Some(causes) = &error.causes {
+                    diagnostics.extend(causes.diagnostics());
+                }
+                diagnostics
+            }
+        }
+    }
+
+    fn static_diagnostics(errors: &[LoxError], phase: Phase) -> Vec<Diagnostic> {
+        errors
+            .iter()
+            .map(|error| Diagnostic::from_error(error, phase))
+            .collect()
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders diagnostics with the offending source line and a caret under the
+/// exact span:
+///
+/// ```text
+/// error: Already a variable with this name in this scope.
+///  --> script.lox:3:9
+///   |
+/// 3 |     var a = 2;
+///   |         ^
+///   |
+/// 2 |     var a = 1;
+///   |         - variable first declared here
+/// ```
+///
+/// or, with [`ErrorFormat::Json`], as one JSON object per line.
+pub struct Renderer<'a> {
+    sources: &'a SourceMap,
+    color: bool,
+    format: ErrorFormat,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(sources: &'a SourceMap, color: bool) -> Self {
+        Self {
+            sources,
+            color,
+            format: ErrorFormat::Human,
+        }
+    }
+
+    pub fn with_format(mut self, format: ErrorFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// A renderer that only uses color when stderr is a terminal.
+    pub fn for_stderr(sources: &'a SourceMap) -> Self {
+        Self::new(sources, io::stderr().is_terminal())
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match self.format {
+            ErrorFormat::Human => self.render_human(diagnostic),
+            ErrorFormat::Json => self.render_json(diagnostic),
+        }
+    }
+
+    fn render_json(&self, diagnostic: &Diagnostic) -> String {
+        let kind = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let span = &diagnostic.span;
+        let lexeme = self
+            .sources
+            .get(&span.source)
+            .and_then(|source| source.get(span.start..span.end))
+            .unwrap_or("");
+
+        let mut out = format!(
+            "{{\"kind\":\"{kind}\",\"phase\":\"{}\",",
+            diagnostic.phase.name()
+        );
+        if let Some(code) = diagnostic.code {
+            let _ = write!(out, "\"code\":{},", json_string(code));
+        }
+        let _ = write!(
+            out,
+            "\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"lexeme\":{}",
+            json_string(&diagnostic.message),
+            json_string(&span.file),
+            span.line,
+            span.column,
+            json_string(lexeme),
+        );
+        if !diagnostic.trace.is_empty() {
+            let frames: Vec<_> = diagnostic
+                .trace
+                .iter()
+                .map(|frame| {
+                    format!(
+                        "{{\"function\":{},\"file\":{},\"line\":{}}}",
+                        json_string(&frame.function),
+                        json_string(&frame.file),
+                        frame.line
+                    )
+                })
+                .collect();
+            let _ = write!(out, ",\"trace\":[{}]", frames.join(","));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_human(&self, diagnostic: &Diagnostic) -> String {
+        let (severity, color) = match diagnostic.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+
+        let width = diagnostic
+            .labels
+            .iter()
+            .map(|label| label.span.line)
+            .chain([diagnostic.span.line])
+            .max()
+            .unwrap_or(0)
+            .to_string()
+            .len();
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{severity}{}: {}{}{}{}",
+            self.paint(color),
+            self.paint(RESET),
+            self.paint(BOLD),
+            diagnostic.message,
+            diagnostic
+                .code
+                .map_or(String::new(), |code| format!(" [{code}]")),
+            self.paint(RESET),
+        );
+        let _ = writeln!(
+            out,
+            "{:width$}{}-->{} {}",
+            "",
+            self.paint(BLUE),
+            self.paint(RESET),
+            diagnostic.span,
+        );
+
+        self.snippet(&mut out, &diagnostic.span, '^', "", color, width);
+        for label in diagnostic.labels.iter() {
+            self.snippet(&mut out, &label.span, '-', &label.message, BLUE, width);
+        }
+
+        if !diagnostic.trace.is_empty() {
+            let _ = writeln!(
+                out,
+                "{:width$} {}|{}",
+                "",
+                self.paint(BLUE),
+                self.paint(RESET)
+            );
+        }
+        for (frame, count) in collapse_frames(&diagnostic.trace) {
+            let repeated = match count {
+                1 => String::new(),
+                _ => format!(" (repeated {count} times)"),
+            };
+            let _ = writeln!(
+                out,
+                "{:width$} {}={} {frame}{repeated}",
+                "",
+                self.paint(BLUE),
+                self.paint(RESET)
+            );
+        }
+
+        out
+    }
+
+    fn snippet(
+        &self,
+        out: &mut String,
+        span: &Span,
+        marker: char,
+        message: &str,
+        color: &'static str,
+        width: usize,
+    ) {
+        let Some(source) = self.sources.get(&span.source) else {
+            return;
+        };
+        let Some(line) = source.lines().nth(span.line.saturating_sub(1) as usize) else {
+            return;
+        };
+
+        // Tabs are kept in the padding so that the marker lines up with the text.
+        let padding: String = line
+            .chars()
+            .take(span.column.saturating_sub(1) as usize)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let length = source
+            .get(span.start..span.end)
+            .map_or(0, |text| text.chars().take_while(|c| *c != '\n').count())
+            .max(1);
+        let mut markers: String = std::iter::repeat_n(marker, length).collect();
+        if !message.is_empty() {
+            markers = format!("{markers} {message}");
+        }
+
+        let gutter = self.paint(BLUE);
+        let reset = self.paint(RESET);
+        let _ = writeln!(out, "{:width$} {gutter}|{reset}", "");
+        let _ = writeln!(out, "{gutter}{:>width$} |{reset} {line}", span.line);
+        let _ = writeln!(
+            out,
+            "{:width$} {gutter}|{reset} {padding}{}{markers}{reset}",
+            "",
+            self.paint(color),
+        );
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.color {
+            code
+        } else {
+            ""
+        }
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
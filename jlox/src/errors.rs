@@ -1,86 +1,159 @@
-use std::fmt::Display;
+use std::{fmt::Display, rc::Rc};
+
+use crate::{
+    diagnostics::{Label, Renderer},
+    scanner::Span,
+    values::{exception, LoxObj},
+};
 
 #[derive(Debug, Clone)]
 pub struct LoxError {
-    line: usize,
+    pub span: Span,
     location: String,
-    message: String,
+    pub message: String,
+    pub labels: Vec<Label>,
+}
+
+impl LoxError {
+    /// Points at another location that helps explain this error.
+    pub fn with_label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
 }
 
 impl Display for LoxError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "[line {}] Error{}: {}",
-            self.line, self.location, self.message
+            "[{}] Error{}: {}",
+            self.span, self.location, self.message
         )
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
-    pub line: usize,
+    pub span: Span,
     pub message: String,
+    /// The value given to `throw`, when the error comes from Lox code.
+    pub thrown: Option<LoxObj>,
+    /// The calls that were being evaluated when the error was raised, innermost first.
+    pub trace: Vec<Frame>,
+    /// The errors that stopped an imported module from loading.
+    pub causes: Option<Box<Errors>>,
+}
+
+/// A call on the interpreter's call stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: String,
+    /// The file and line of the call site.
+    pub file: Rc<str>,
+    pub line: u32,
+}
+
+/// Groups consecutive identical frames, as left by deep recursion, so that
+/// traces stay readable. Each frame comes with how many times it repeats.
+pub fn collapse_frames(frames: &[Frame]) -> Vec<(&Frame, usize)> {
+    let mut collapsed: Vec<(&Frame, usize)> = Vec::new();
+    for frame in frames {
+        match collapsed.last_mut() {
+            Some((last, count)) if *last == frame => *count += 1,
+            _ => collapsed.push((frame, 1)),
+        }
+    }
+    collapsed
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at {} ({}:{})", self.function, self.file, self.line)
+    }
+}
+
+impl RuntimeError {
+    /// The value a `catch` clause receives for this error.
+    pub fn into_value(self) -> LoxObj {
+        self.thrown
+            .unwrap_or_else(|| exception(self.message, self.span))
+    }
 }
 
 impl Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}\n[line {}]", self.message, self.line)
+        write!(f, "{}\n[{}]", self.message, self.span)?;
+        collapse_frames(&self.trace)
+            .into_iter()
+            .try_for_each(|(frame, count)| match count {
+                1 => write!(f, "\n{frame}"),
+                _ => write!(f, "\n{frame} (repeated {count} times)"),
+            })
     }
 }
 
+#[derive(Debug, Clone)]
 pub enum Errors {
+    Scanning(Vec<LoxError>),
     Parsing(Vec<LoxError>),
+    Resolving(Vec<LoxError>),
     Runtime(RuntimeError),
 }
 
 impl Display for Errors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Errors::Parsing(errors) => errors.iter().fold(Ok(()), |res, error| {
-                res.and_then(|_| writeln!(f, "{error}"))
-            }),
+            Errors::Scanning(errors) | Errors::Parsing(errors) | Errors::Resolving(errors) => {
+                errors.iter().try_for_each(|error| writeln!(f, "{error}"))
+            }
             Errors::Runtime(error) => writeln!(f, "{error}"),
         }
     }
 }
 
-pub fn error(line: usize, message: &str) -> LoxError {
+pub fn error(span: Span, message: &str) -> LoxError {
     LoxError {
-        line,
+        span,
         location: String::new(),
         message: message.into(),
+        labels: Vec::new(),
     }
 }
 
-pub fn error_with_location(line: usize, location: &impl AsRef<str>, message: &str) -> LoxError {
+pub fn error_with_location(span: Span, location: &impl AsRef<str>, message: &str) -> LoxError {
     LoxError {
-        line,
+        span,
         location: location.as_ref().into(),
         message: message.into(),
+        labels: Vec::new(),
     }
 }
 
 pub trait ReportErrors {
-    fn report(&self);
-    fn report_and_exit(&self);
+    fn report(&self, renderer: &Renderer);
+    fn report_and_exit(&self, renderer: &Renderer);
 }
 
 impl<T> ReportErrors for Result<T, Errors> {
-    fn report(&self) {
+    fn report(&self, renderer: &Renderer) {
         if let Err(errors) = self {
-            eprint!("{}", errors);
+            for diagnostic in errors.diagnostics() {
+                eprint!("{}", renderer.render(&diagnostic));
+            }
         }
     }
 
-    fn report_and_exit(&self) {
+    fn report_and_exit(&self, renderer: &Renderer) {
         if let Err(errors) = self {
             let code = match errors {
                 Errors::Runtime(_) => 70,
                 _ => 65,
             };
 
-            eprint!("{}", errors);
+            self.report(renderer);
             std::process::exit(code);
         }
     }
@@ -1,70 +1,309 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
-
-use chrono::Utc;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use crate::{
     ast::{Expr, ExprVisitor, Stmt, StmtVisitor},
-    errors::RuntimeError,
-    scanner::{Token, TokenType},
-    values::{boolean, lox_class, lox_fn, native_fn, nil, number, LoxObj, LoxProperty},
+    diagnostics::SourceMap,
+    errors::{Errors, Frame, RuntimeError},
+    parser,
+    resolver::Resolver,
+    scanner::{scan_tokens, Token, TokenType},
+    stdlib,
+    values::{
+        boolean, list, lox_class, lox_fn, lox_method, map, module, native_fn, nil, string,
+        IntoNative, LoxObj, LoxProperty,
+    },
+    warnings::Warning,
 };
 
+/// How the execution of a statement finished. Anything other than `Normal`
+/// unwinds the enclosing statements until something handles it: a loop takes
+/// `Break` and `Continue`, a function call takes `Return`.
+pub enum Completion {
+    Normal,
+    Return { keyword: Token, value: LoxObj },
+    Break { keyword: Token },
+    Continue { keyword: Token },
+    Throw { keyword: Token, value: LoxObj },
+}
+
+impl Completion {
+    /// Turns a completion that escaped everything able to handle it into an error.
+    pub fn expect_normal(self) -> Result<(), RuntimeError> {
+        match self {
+            Completion::Normal => Ok(()),
+            Completion::Return { keyword, .. } => {
+                Err(runtime_error(&keyword, "Can't return from top-level code."))
+            }
+            Completion::Break { keyword } => Err(runtime_error(
+                &keyword,
+                "Can't use 'break' outside of a loop.",
+            )),
+            Completion::Continue { keyword } => Err(runtime_error(
+                &keyword,
+                "Can't use 'continue' outside of a loop.",
+            )),
+            Completion::Throw { keyword, value } => Err(RuntimeError {
+                span: keyword.span,
+                message: format!("Uncaught exception: {value}"),
+                thrown: Some(value),
+                trace: Vec::new(),
+                causes: None,
+            }),
+        }
+    }
+}
+
 pub struct Interpreter {
+    /// The native functions, shared by the globals of every module.
+    pub builtins: Rc<RefCell<Environment>>,
     pub globals: Rc<RefCell<Environment>>,
+    /// The globals of the module whose code is running, which are `globals`
+    /// outside of imported modules.
+    module_globals: Rc<RefCell<Environment>>,
     pub environment: Rc<RefCell<Environment>>,
     pub locals: HashMap<Expr, usize>,
+    /// The source of every file that was run, including imported modules.
+    pub sources: SourceMap,
+    current_call: Option<Token>,
+    call_stack: Vec<Frame>,
+    max_call_depth: usize,
+    /// Loaded modules, by canonical path.
+    modules: HashMap<PathBuf, LoxObj>,
+    /// The modules being loaded, outermost first, to detect import cycles.
+    loading: Vec<(PathBuf, String)>,
+    /// Warnings about imported modules, found as they are loaded.
+    warnings: Vec<Warning>,
 }
 
+/// How many calls can be nested before a "Stack overflow." error.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Every Lox call recurses on the Rust stack, taking tens of kilobytes in a
+/// debug build. Before a call, the stack is grown by `STACK_SEGMENT` bytes
+/// when less than `STACK_RED_ZONE` is left, so the call depth is limited by
+/// `max_call_depth` alone, whatever thread the interpreter runs on.
+const STACK_RED_ZONE: usize = 512 * 1024;
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
 pub fn runtime_error(token: &Token, message: &str) -> RuntimeError {
     RuntimeError {
-        line: token.line,
+        span: token.span.clone(),
         message: message.to_string(),
+        thrown: None,
+        trace: Vec::new(),
+        causes: None,
     }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let environment = Environment::new();
-        environment.borrow_mut().define(
-            "clock".into(),
-            native_fn(0, |_, _| {
-                Ok(number(Utc::now().timestamp_millis() as f64 / 1000.0))
+        let builtins = Environment::new();
+        let environment = Environment::with_enclosing(builtins.clone());
+        let mut interpreter = Self {
+            builtins,
+            environment: environment.clone(),
+            globals: environment.clone(),
+            module_globals: environment,
+            locals: HashMap::new(),
+            sources: SourceMap::new(),
+            current_call: None,
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        stdlib::define_globals(&mut interpreter);
+        interpreter
+    }
+
+    /// Defines a global native function from a Rust closure, converting its
+    /// arguments with `FromLox` and its return value with `IntoLox`.
+    pub fn define_native<Args>(&mut self, name: &str, function: impl IntoNative<Args> + 'static) {
+        let arity = function.arity();
+        self.builtins.borrow_mut().define(
+            name.into(),
+            native_fn(name.into(), arity, move |interpreter, args| {
+                function.invoke(interpreter, args)
             }),
         );
+    }
 
-        Self {
-            environment: environment.clone(),
-            globals: environment,
-            locals: HashMap::new(),
+    /// Sets how many calls can be nested before raising "Stack overflow.".
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Builds a runtime error located at the call currently being evaluated,
+    /// for errors raised by native functions.
+    pub fn native_error(&self, message: &str) -> RuntimeError {
+        RuntimeError {
+            span: self
+                .current_call
+                .as_ref()
+                .map(|token| token.span.clone())
+                .unwrap_or_default(),
+            message: message.to_string(),
+            thrown: None,
+            trace: Vec::new(),
+            causes: None,
         }
     }
 
     pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
         for statement in statements {
-            self.visit_stmt(statement)?;
+            self.visit_stmt(statement)?.expect_normal()?;
         }
 
         Ok(())
     }
 
+    /// Runs the file at `path`, relative to the file importing it, and returns
+    /// its module. Each file only runs once; later imports share the module.
+    fn import(&mut self, path: &Token) -> Result<LoxObj, RuntimeError> {
+        let TokenType::String(relative) = &path.token_type else {
+            unreachable!("the parser only accepts string paths")
+        };
+
+        // Paths in the REPL are relative to the working directory.
+        let importer = Path::new(&*path.span.file);
+        let location = match importer.parent() {
+            Some(directory) if !path.span.file.starts_with('<') => directory.join(relative),
+            _ => PathBuf::from(relative),
+        };
+
+        let Ok(canonical) = location.canonicalize() else {
+            return Err(runtime_error(
+                path,
+                &format!("Could not find module '{relative}'."),
+            ));
+        };
+
+        if let Some(module) = self.modules.get(&canonical) {
+            return Ok(module.clone());
+        }
+
+        if let Some(start) = self
+            .loading
+            .iter()
+            .position(|(loading, _)| *loading == canonical)
+        {
+            let mut cycle: Vec<_> = self.loading[start..]
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .collect();
+            cycle.push(&self.loading[start].1);
+            return Err(runtime_error(
+                path,
+                &format!("Import cycle: {}.", cycle.join(" -> ")),
+            ));
+        }
+
+        let source = fs::read_to_string(&canonical).map_err(|error| {
+            runtime_error(
+                path,
+                &format!("Could not read module '{relative}': {error}"),
+            )
+        })?;
+
+        let name = location.display().to_string();
+        self.loading.push((canonical.clone(), name.clone()));
+        let globals = self
+            .run_module(&name, source)
+            .map_err(|errors| match errors {
+                // Errors at runtime already point into the module.
+                Errors::Runtime(error) => error,
+                errors => RuntimeError {
+                    causes: Some(Box::new(errors)),
+                    ..runtime_error(path, &format!("Could not load module '{relative}'."))
+                },
+            });
+        self.loading.pop();
+
+        let name = location
+            .file_stem()
+            .map_or(name, |stem| stem.to_string_lossy().into_owned());
+        let module = module(name, globals?);
+        self.modules.insert(canonical, module.clone());
+        Ok(module)
+    }
+
+    /// Runs a module's source with its own globals, which it returns.
+    fn run_module(
+        &mut self,
+        name: &str,
+        source: String,
+    ) -> Result<Rc<RefCell<Environment>>, Errors> {
+        let result = scan_tokens(source.clone(), name.into());
+        self.sources.insert(result.source_id(), source);
+
+        let mut errors = Vec::new();
+        let tokens = result.unwrap(&mut errors);
+        if !errors.is_empty() {
+            return Err(Errors::Scanning(errors));
+        }
+
+        let statements = parser::parse(tokens)?;
+        let mut resolver = Resolver::new(self);
+        let resolved = resolver.resolve(&statements);
+        let warnings = resolver.take_warnings();
+        self.warnings.extend(warnings);
+        resolved.map_err(Errors::Resolving)?;
+
+        let globals = Environment::with_enclosing(self.builtins.clone());
+        let enclosing = std::mem::replace(&mut self.environment, globals.clone());
+        let importer_globals = self.enter_module(globals.clone());
+        let result = self.interpret(&statements);
+        self.enter_module(importer_globals);
+        self.environment = enclosing;
+
+        result.map_err(Errors::Runtime)?;
+        Ok(globals)
+    }
+
+    pub fn evaluate(&mut self, expression: &Expr) -> Result<LoxObj, RuntimeError> {
+        self.visit_expr(expression)
+    }
+
     pub fn execute_block(
         &mut self,
         statements: &[Stmt],
         environment: Rc<RefCell<Environment>>,
-    ) -> Result<Option<LoxObj>, RuntimeError> {
-        let previous = self.environment.clone();
-        self.environment = environment;
+    ) -> Result<Completion, RuntimeError> {
+        let previous = std::mem::replace(&mut self.environment, environment);
 
-        let mut return_value = None;
+        let mut completion = Ok(Completion::Normal);
         for statement in statements.iter() {
-            if let Some(value) = self.visit_stmt(statement)? {
-                return_value = Some(value);
+            completion = self.visit_stmt(statement);
+            if !matches!(completion, Ok(Completion::Normal)) {
                 break;
             }
         }
 
         self.environment = previous;
-        Ok(return_value)
+        completion
+    }
+
+    fn execute_catch(
+        &mut self,
+        name: &Token,
+        body: &[Stmt],
+        value: LoxObj,
+    ) -> Result<Completion, RuntimeError> {
+        let environment = Environment::with_enclosing(self.environment.clone());
+        environment.borrow_mut().define(name.lexeme.clone(), value);
+        self.execute_block(body, environment)
     }
 
     pub fn resolve(&mut self, expr: &Expr, depth: usize) {
@@ -77,16 +316,33 @@ impl Interpreter {
                 .borrow()
                 .get_at(*distance, name.lexeme.clone())
         } else {
-            self.globals.borrow().get(name)
+            self.module_globals.borrow().get(name)
         }
     }
+
+    /// The warnings about modules imported since the last call.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Makes `globals` the globals of the running code, for calls into a
+    /// function from another module. Returns the globals to restore after.
+    pub fn enter_module(&mut self, globals: Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        std::mem::replace(&mut self.module_globals, globals)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ExprVisitor<Result<LoxObj, RuntimeError>> for Interpreter {
     fn visit_expr(&mut self, expr: &Expr) -> Result<LoxObj, RuntimeError> {
         match expr {
-            Expr::Literal { value } => Ok(value.clone()),
-            Expr::Grouping { expression } => self.visit_expr(expression),
+            Expr::Literal { value, .. } => Ok(value.clone()),
+            Expr::Grouping { expression, .. } => self.visit_expr(expression),
 
             Expr::Unary { operator, right } => {
                 let result = self.visit_expr(right)?;
@@ -144,7 +400,19 @@ impl ExprVisitor<Result<LoxObj, RuntimeError>> for Interpreter {
                         .borrow_mut()
                         .assign_at(*distance, name, value)
                 } else {
-                    self.globals.borrow_mut().assign(name, value)
+                    let globals = self.module_globals.clone();
+                    let shadows_builtin = globals.borrow().get_value(&name.lexeme).is_none()
+                        && self.builtins.borrow().get_value(&name.lexeme).is_some();
+
+                    // Assigning to a native function only replaces it in this module.
+                    if shadows_builtin {
+                        globals
+                            .borrow_mut()
+                            .define(name.lexeme.clone(), value.clone());
+                        Ok(value)
+                    } else {
+                        globals.borrow_mut().assign(name, value)
+                    }
                 }
             }
 
@@ -182,8 +450,29 @@ impl ExprVisitor<Result<LoxObj, RuntimeError>> for Interpreter {
                                 args.len()
                             ),
                         ))
+                    } else if self.call_stack.len() >= self.max_call_depth {
+                        Err(runtime_error(paren, "Stack overflow."))
                     } else {
-                        callee.call(self, &args)
+                        self.current_call = Some(paren.clone());
+                        self.call_stack.push(Frame {
+                            function: callee.name(),
+                            file: paren.span.file.clone(),
+                            line: paren.span.line,
+                        });
+
+                        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+                            callee.call(self, &args)
+                        });
+                        let result = result.map_err(|mut error| {
+                            // Only the innermost call sees the whole stack.
+                            if error.trace.is_empty() {
+                                error.trace = self.call_stack.iter().rev().cloned().collect();
+                            }
+                            error
+                        });
+
+                        self.call_stack.pop();
+                        result
                     }
                 } else {
                     Err(runtime_error(paren, "Can only call functions and classes."))
@@ -221,6 +510,65 @@ impl ExprVisitor<Result<LoxObj, RuntimeError>> for Interpreter {
                     .ok_or(runtime_error(name, "Only instances have fields."))
             }
 
+            Expr::Interpolation { parts } => {
+                let mut text = String::new();
+                for part in parts.iter() {
+                    text += &self.visit_expr(part)?.to_string();
+                }
+                Ok(string(text))
+            }
+
+            Expr::List { elements, .. } => {
+                let mut values = Vec::new();
+                for element in elements.iter() {
+                    values.push(self.visit_expr(element)?);
+                }
+                Ok(list(values))
+            }
+
+            Expr::Map { entries, .. } => {
+                let mut values = Vec::new();
+                for (key, value) in entries.iter() {
+                    values.push((self.visit_expr(key)?, self.visit_expr(value)?));
+                }
+                Ok(map(values))
+            }
+
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                object
+                    .get_index(&index)
+                    .map_err(|message| runtime_error(bracket, &message))
+            }
+
+            Expr::IndexSet {
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                let value = self.visit_expr(value)?;
+                object
+                    .set_index(&index, &value)
+                    .map_err(|message| runtime_error(bracket, &message))
+            }
+
+            Expr::Lambda { params, body, .. } => Ok(lox_fn(
+                None,
+                params.clone(),
+                body.clone(),
+                self.environment.clone(),
+                self.module_globals.clone(),
+                false,
+            )),
+
             Expr::This { keyword } => self.lookup_variable(keyword, expr),
 
             Expr::Super { keyword, method } => {
@@ -252,29 +600,57 @@ impl ExprVisitor<Result<LoxObj, RuntimeError>> for Interpreter {
     }
 }
 
-impl StmtVisitor<Result<Option<LoxObj>, RuntimeError>> for Interpreter {
-    fn visit_stmt(&mut self, statement: &Stmt) -> Result<Option<LoxObj>, RuntimeError> {
+impl StmtVisitor<Result<Completion, RuntimeError>> for Interpreter {
+    fn visit_stmt(&mut self, statement: &Stmt) -> Result<Completion, RuntimeError> {
         match statement {
             Stmt::Expression { expression } => {
                 self.visit_expr(expression)?;
-                Ok(None)
+                Ok(Completion::Normal)
             }
 
-            Stmt::Print { expression } => {
+            Stmt::Print { expression, .. } => {
                 let value = self.visit_expr(expression)?;
                 println!("{value}");
-                Ok(None)
+                Ok(Completion::Normal)
+            }
+
+            Stmt::Import { path, name, .. } => {
+                let module = self.import(path)?;
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.clone(), module);
+                Ok(Completion::Normal)
+            }
+
+            Stmt::FromImport { path, names, .. } => {
+                let module = self.import(path)?;
+                for name in names {
+                    let property = module.0.borrow().get_property(name);
+                    let LoxProperty::Field(value) = property else {
+                        return Err(runtime_error(
+                            name,
+                            &format!("Module {} has no '{}'.", path.lexeme, name.lexeme),
+                        ));
+                    };
+
+                    self.environment
+                        .borrow_mut()
+                        .define(name.lexeme.clone(), value);
+                }
+                Ok(Completion::Normal)
             }
 
-            Stmt::Var { name, initializer } => {
+            Stmt::Var {
+                name, initializer, ..
+            } => {
                 let value = self.visit_expr(initializer)?;
                 self.environment
                     .borrow_mut()
                     .define(name.lexeme.clone(), value);
-                Ok(None)
+                Ok(Completion::Normal)
             }
 
-            Stmt::Block { statements } => self.execute_block(
+            Stmt::Block { statements, .. } => self.execute_block(
                 statements,
                 Environment::with_enclosing(self.environment.clone()),
             ),
@@ -283,43 +659,117 @@ impl StmtVisitor<Result<Option<LoxObj>, RuntimeError>> for Interpreter {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 let condition_value = self.visit_expr(condition)?;
-                let res = if condition_value.0.borrow().is_truthy() {
-                    self.visit_stmt(then_branch)?
+                if condition_value.0.borrow().is_truthy() {
+                    self.visit_stmt(then_branch)
                 } else if let Some(else_branch) = else_branch {
-                    self.visit_stmt(else_branch)?
+                    self.visit_stmt(else_branch)
                 } else {
-                    None
-                };
-
-                Ok(res)
+                    Ok(Completion::Normal)
+                }
             }
 
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
                 while self.visit_expr(condition)?.0.borrow().is_truthy() {
-                    if let Some(value) = self.visit_stmt(body)? {
-                        return Ok(Some(value));
+                    match self.visit_stmt(body)? {
+                        Completion::Normal | Completion::Continue { .. } => {}
+                        Completion::Break { .. } => break,
+                        completion => return Ok(completion),
+                    }
+
+                    if let Some(increment) = increment {
+                        self.visit_expr(increment)?;
                     }
                 }
-                Ok(None)
+                Ok(Completion::Normal)
             }
 
-            Stmt::Function { name, .. } => {
-                let rc = self.environment.clone();
-                let function = lox_fn(Box::new(statement.clone()), rc, false);
+            Stmt::Break { keyword } => Ok(Completion::Break {
+                keyword: keyword.clone(),
+            }),
+
+            Stmt::Continue { keyword } => Ok(Completion::Continue {
+                keyword: keyword.clone(),
+            }),
+
+            Stmt::Function {
+                name, params, body, ..
+            } => {
+                let function = lox_fn(
+                    Some(name.lexeme.clone()),
+                    params.clone(),
+                    body.clone(),
+                    self.environment.clone(),
+                    self.module_globals.clone(),
+                    false,
+                );
                 self.environment
                     .borrow_mut()
                     .define(name.lexeme.clone(), function);
-                Ok(None)
+                Ok(Completion::Normal)
             }
 
-            Stmt::Return { expression, .. } => Ok(Some(self.visit_expr(expression)?)),
+            Stmt::Return {
+                keyword,
+                expression,
+            } => Ok(Completion::Return {
+                keyword: keyword.clone(),
+                value: self.visit_expr(expression)?,
+            }),
+
+            Stmt::Throw {
+                keyword,
+                expression,
+            } => Ok(Completion::Throw {
+                keyword: keyword.clone(),
+                value: self.visit_expr(expression)?,
+            }),
+
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                let completion =
+                    self.execute_block(body, Environment::with_enclosing(self.environment.clone()));
+
+                let completion = match (catch, completion) {
+                    (Some((name, catch_body)), Ok(Completion::Throw { value, .. })) => {
+                        self.execute_catch(name, catch_body, value)
+                    }
+                    (Some((name, catch_body)), Err(error)) => {
+                        self.execute_catch(name, catch_body, error.into_value())
+                    }
+                    (_, completion) => completion,
+                };
+
+                if let Some(finally) = finally {
+                    // An abrupt finally block overrides how the try statement completed.
+                    match self.execute_block(
+                        finally,
+                        Environment::with_enclosing(self.environment.clone()),
+                    )? {
+                        Completion::Normal => {}
+                        other => return Ok(other),
+                    }
+                }
+
+                completion
+            }
 
             Stmt::Class {
                 name,
                 methods,
                 superclass,
+                ..
             } => {
                 self.environment
                     .borrow_mut()
@@ -334,31 +784,38 @@ impl StmtVisitor<Result<Option<LoxObj>, RuntimeError>> for Interpreter {
                     };
 
                     let class = self.visit_expr(&superclass)?;
+                    let Some(superclass) = class.class() else {
+                        return Err(runtime_error(&name, "Superclass must be a class."));
+                    };
+
                     let enclosing = self.environment.clone();
                     self.environment = Environment::with_enclosing(enclosing.clone());
                     enclosing_env = Some(enclosing);
-                    self.environment
-                        .borrow_mut()
-                        .define("super".into(), class.clone());
+                    self.environment.borrow_mut().define("super".into(), class);
 
-                    if let Some(class) = class.class() {
-                        Some(Box::new(class))
-                    } else {
-                        return Err(runtime_error(&name, "Superclass must be a class."));
-                    }
+                    Some(Box::new(superclass))
                 } else {
                     None
                 };
 
                 let mut class_methods = HashMap::new();
                 for method in methods.iter() {
-                    if let Stmt::Function { name, .. } = method {
-                        let method = lox_fn(
-                            Box::new(method.clone()),
+                    if let Stmt::Function {
+                        name: method_name,
+                        params,
+                        body,
+                        ..
+                    } = method
+                    {
+                        let method = lox_method(
+                            &name.lexeme,
+                            method_name,
+                            params.clone(),
+                            body.clone(),
                             self.environment.clone(),
-                            name.lexeme == "init",
+                            self.module_globals.clone(),
                         );
-                        class_methods.insert(name.lexeme.clone(), method);
+                        class_methods.insert(method_name.lexeme.clone(), method);
                     }
                 }
 
@@ -369,7 +826,7 @@ impl StmtVisitor<Result<Option<LoxObj>, RuntimeError>> for Interpreter {
                 }
 
                 self.environment.borrow_mut().assign(name, class)?;
-                Ok(None)
+                Ok(Completion::Normal)
             }
         }
     }
@@ -400,6 +857,15 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    pub fn get_value(&self, name: &str) -> Option<LoxObj> {
+        self.values.get(name).cloned()
+    }
+
+    /// The variables defined directly in this environment, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = (&String, &LoxObj)> {
+        self.values.iter()
+    }
+
     pub fn get(&self, name: &Token) -> Result<LoxObj, RuntimeError> {
         let error_msg = format!("Undefined variable '{}'.", name.lexeme);
         if let Some(value) = self.values.get(&name.lexeme) {
@@ -424,8 +890,8 @@ impl Environment {
     }
 
     fn ancestor(&self, distance: usize) -> Option<Rc<RefCell<Environment>>> {
-        let mut environment = Some(Rc::new(RefCell::new(self.clone())));
-        for _ in 0..distance {
+        let mut environment = self.enclosing.clone();
+        for _ in 1..distance {
             if let Some(env) = environment {
                 environment = env.borrow().enclosing.clone();
             }
@@ -434,7 +900,9 @@ impl Environment {
     }
 
     pub fn get_at(&self, distance: usize, name: String) -> Result<LoxObj, RuntimeError> {
-        if let Some(ancestor) = self.ancestor(distance) {
+        if distance == 0 {
+            Ok(self.values.get(&name).unwrap().clone())
+        } else if let Some(ancestor) = self.ancestor(distance) {
             Ok(ancestor.borrow().values.get(&name).unwrap().clone())
         } else {
             unreachable!()
@@ -442,12 +910,15 @@ impl Environment {
     }
 
     pub fn assign_at(
-        &self,
+        &mut self,
         distance: usize,
         name: &Token,
         value: LoxObj,
     ) -> Result<LoxObj, RuntimeError> {
-        if let Some(ancestor) = self.ancestor(distance) {
+        if distance == 0 {
+            self.values.insert(name.lexeme.clone(), value.clone());
+            Ok(value)
+        } else if let Some(ancestor) = self.ancestor(distance) {
             ancestor
                 .borrow_mut()
                 .values
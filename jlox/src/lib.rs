@@ -0,0 +1,282 @@
+use std::collections::{BTreeMap, HashSet};
+
+use ast::{Expr, Stmt};
+use diagnostics::{ErrorFormat, Renderer};
+use interpreter::Interpreter;
+use resolver::Resolver;
+use scanner::Token;
+use values::{FromLox, IntoLox, IntoNative, LoxObj};
+use warnings::{Warning, WarningKind};
+
+pub mod ast;
+pub mod ast_printer;
+pub mod diagnostics;
+pub mod errors;
+pub mod interpreter;
+pub mod parser;
+pub mod resolver;
+pub mod scanner;
+pub mod stdlib;
+pub mod values;
+pub mod warnings;
+
+pub use errors::{Errors, LoxError, ReportErrors, RuntimeError};
+
+/// An embeddable Lox engine. Every call to [`Lox::run`] shares the same
+/// interpreter, so globals defined by one source string are visible to the next.
+pub struct Lox {
+    interpreter: Interpreter,
+    warnings: Vec<Warning>,
+    allowed: HashSet<WarningKind>,
+    error_format: ErrorFormat,
+    /// How many sources have been given a name by [`Lox::unique_name`].
+    unnamed: usize,
+}
+
+impl Lox {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            warnings: Vec::new(),
+            allowed: HashSet::new(),
+            error_format: ErrorFormat::Human,
+            unnamed: 0,
+        }
+    }
+
+    /// Scans, parses, resolves and executes `source`. Diagnostics call it
+    /// `<input:N>` for the `N`th source run without a name.
+    pub fn run(&mut self, source: impl Into<String>) -> Result<(), Errors> {
+        let name = self.unique_name("input");
+        self.run_named(&name, source)
+    }
+
+    /// Like [`Lox::run`], with `name` as the file reported in diagnostics.
+    pub fn run_named(&mut self, name: &str, source: impl Into<String>) -> Result<(), Errors> {
+        let tokens = self.scan(name, source)?;
+        let statements = parser::parse(tokens)?;
+        self.resolve(&statements)?;
+
+        let result = self.interpreter.interpret(&statements);
+        self.add_module_warnings();
+        result.map_err(Errors::Runtime)
+    }
+
+    /// Runs a line typed at the REPL, named `<repl:N>` in diagnostics. When
+    /// the line is a single expression, with or without its trailing `;`, its
+    /// value is returned so that it can be echoed back.
+    pub fn run_line(&mut self, source: impl Into<String>) -> Result<Option<LoxObj>, Errors> {
+        let name = self.unique_name("repl");
+        let tokens = self.scan(&name, source)?;
+
+        let Ok(expression) = parser::parse_expression(tokens.clone()) else {
+            let statements = parser::parse(tokens)?;
+            self.resolve(&statements)?;
+            let result = self.interpreter.interpret(&statements);
+            self.add_module_warnings();
+            return result.map(|_| None).map_err(Errors::Runtime);
+        };
+
+        // The interpreter looks resolved expressions up by value, so the
+        // statement can hold a copy.
+        self.resolve(&[Stmt::Expression {
+            expression: Box::new(expression.clone()),
+        }])?;
+
+        let value = self.interpreter.evaluate(&expression);
+        self.add_module_warnings();
+        value.map(Some).map_err(Errors::Runtime)
+    }
+
+    /// A name for source that doesn't come from a file, numbered so that
+    /// diagnostics and traces tell the runs apart.
+    fn unique_name(&mut self, kind: &str) -> String {
+        self.unnamed += 1;
+        format!("<{kind}:{}>", self.unnamed)
+    }
+
+    /// Parses `source` as a single expression, without running it.
+    pub fn parse_expression(
+        &mut self,
+        name: &str,
+        source: impl Into<String>,
+    ) -> Result<Expr, Errors> {
+        let tokens = self.scan(name, source)?;
+        parser::parse_expression(tokens)
+    }
+
+    /// Scans `source` into tokens, keeping it to show in diagnostics.
+    pub fn scan(&mut self, name: &str, source: impl Into<String>) -> Result<Vec<Token>, Errors> {
+        let source = source.into();
+        let result = scanner::scan_tokens(source.clone(), name.into());
+        self.interpreter.sources.insert(result.source_id(), source);
+
+        let mut errors = Vec::new();
+        let tokens = result.unwrap(&mut errors);
+
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(Errors::Scanning(errors))
+        }
+    }
+
+    fn resolve(&mut self, statements: &[Stmt]) -> Result<(), Errors> {
+        let mut resolver = Resolver::new(&mut self.interpreter);
+        let resolved = resolver.resolve(statements);
+
+        let warnings = resolver.take_warnings();
+        self.add_warnings(warnings);
+
+        resolved.map_err(Errors::Resolving)
+    }
+
+    fn add_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings.extend(
+            warnings
+                .into_iter()
+                .filter(|warning| !self.allowed.contains(&warning.kind)),
+        );
+    }
+
+    /// Keeps the warnings about modules imported while running.
+    fn add_module_warnings(&mut self) {
+        let warnings = self.interpreter.take_warnings();
+        self.add_warnings(warnings);
+    }
+
+    /// Stops warnings of this kind from being reported.
+    pub fn allow(&mut self, kind: WarningKind) {
+        self.allowed.insert(kind);
+    }
+
+    /// The warnings found since the last call. They never affect whether a
+    /// program runs.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Every global variable, including the native functions, sorted by name.
+    pub fn globals(&self) -> Vec<(String, LoxObj)> {
+        let builtins = self.interpreter.builtins.borrow();
+        let globals = self.interpreter.globals.borrow();
+
+        // A global can shadow a native function of the same name.
+        let mut all = BTreeMap::new();
+        for (name, value) in builtins.values().chain(globals.values()) {
+            all.insert(name.clone(), value.clone());
+        }
+        all.into_iter().collect()
+    }
+
+    /// Starts over with a fresh interpreter, forgetting every global. Natives
+    /// added with [`Lox::define_native`] have to be defined again.
+    pub fn reset(&mut self) {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(self.interpreter.max_call_depth());
+        interpreter.sources = std::mem::take(&mut self.interpreter.sources);
+        self.interpreter = interpreter;
+    }
+
+    /// The global `name` as a Rust value, or `None` when it isn't defined or
+    /// doesn't convert to `T`. Use `LoxObj` for `T` to get any value as is.
+    pub fn get_global<T: FromLox>(&self, name: &str) -> Option<T> {
+        let global = self.interpreter.globals.borrow().get_value(name);
+        let value = global.or_else(|| self.interpreter.builtins.borrow().get_value(name))?;
+        T::from_lox(&value)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: impl IntoLox) {
+        self.interpreter
+            .globals
+            .borrow_mut()
+            .define(name.into(), value.into_lox());
+    }
+
+    pub fn define_native<Args>(&mut self, name: &str, function: impl IntoNative<Args> + 'static) {
+        self.interpreter.define_native(name, function);
+    }
+
+    /// Sets the format used by [`Lox::renderer`].
+    pub fn set_error_format(&mut self, format: ErrorFormat) {
+        self.error_format = format;
+    }
+
+    /// A renderer for this engine's errors, showing the source they point at.
+    pub fn renderer(&self) -> Renderer<'_> {
+        Renderer::for_stderr(&self.interpreter.sources).with_format(self.error_format)
+    }
+
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reruns_a_name_with_different_source() {
+        let mut lox = Lox::new();
+        lox.run_named("a", "var seen; { var a = 1; { seen = a; } }")
+            .unwrap();
+        // `a` here has the span of the local `a` above, but is a global.
+        lox.run_named("a", "var a = 2;               seen = a;")
+            .unwrap();
+        assert_eq!(lox.get_global::<f64>("seen"), Some(2.0));
+    }
+
+    #[test]
+    fn renders_the_source_each_run_had() {
+        let mut lox = Lox::new();
+        lox.run_named("a", "fun f() { return 1 + nil; }").unwrap();
+        lox.run_named("a", "print 1;").unwrap();
+
+        let errors = lox.run_named("b", "f();").unwrap_err();
+        let rendered = lox.renderer().render(&errors.diagnostics()[0]);
+        assert!(
+            rendered.contains("fun f() { return 1 + nil; }"),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn spans_cover_leading_keywords_and_brackets() {
+        let source = "fun f() { return; print [(1), {}]; }";
+        let mut lox = Lox::new();
+        lox.run(source).unwrap();
+
+        let warnings = lox.take_warnings();
+        let span = &warnings[0].span;
+        assert_eq!(&source[span.start..span.end], "print [(1), {}]");
+    }
+
+    #[test]
+    fn reports_every_error_in_a_lambda() {
+        let mut lox = Lox::new();
+        let errors = lox
+            .run("var f = fun () { var = 1; print; }; print;")
+            .unwrap_err();
+
+        let messages: Vec<_> = errors
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Expect variable name.",
+                "Expect expression.",
+                "Expect expression."
+            ]
+        );
+    }
+}
@@ -1,68 +1,66 @@
 use std::{env, fs, println};
 
-use errors::{Errors, ReportErrors};
-use interpreter::Interpreter;
-use resolver::Resolver;
-use rustyline::{DefaultEditor, Result as RLResult};
+use jlox::{
+    diagnostics::{Diagnostic, ErrorFormat},
+    warnings::WarningKind,
+    Lox, ReportErrors,
+};
 
-mod ast;
-mod ast_printer;
-mod errors;
-mod interpreter;
-mod parser;
-mod resolver;
-mod scanner;
-mod values;
+mod repl;
 
-fn run_file(path: &str) {
-    let mut interpreter = Interpreter::new();
-    let source = fs::read_to_string(path).unwrap();
-    run(&mut interpreter, source).report_and_exit();
-}
-
-fn run_prompt() -> RLResult<()> {
-    let mut interpreter = Interpreter::new();
-    let mut rl = DefaultEditor::new().unwrap();
-    loop {
-        let line = rl.readline("> ")?;
-        rl.add_history_entry(line.clone())?;
-        run(&mut interpreter, line).report();
+fn report_warnings(lox: &mut Lox) {
+    let warnings = lox.take_warnings();
+    let renderer = lox.renderer();
+    for warning in warnings.iter() {
+        eprint!("{}", renderer.render(&Diagnostic::from(warning)));
     }
 }
 
-fn run(interpreter: &mut Interpreter, source: String) -> Result<(), Errors> {
-    let mut errors = Vec::new();
-    let tokens = scanner::scan_tokens(source).unwrap(&mut errors);
-
-    if !errors.is_empty() {
-        return Err(Errors::Parsing(errors));
-    }
-
-    let statements = parser::parse(tokens)?;
-
-    let mut resolver = Resolver::new(interpreter);
-    resolver
-        .resolve(&statements)
-        .map_err(|err| Errors::Parsing(vec![err]))?;
-
-    interpreter
-        .interpret(&statements)
-        .map_err(Errors::Runtime)?;
+fn run_file(mut lox: Lox, path: &str) {
+    let source = fs::read_to_string(path).unwrap();
+    let result = lox.run_named(path, source);
+    report_warnings(&mut lox);
+    result.report_and_exit(&lox.renderer());
+}
 
-    Ok(())
+fn usage() -> ! {
+    println!("Usage: jlox [--allow=<warning>[,<warning>...]] [--error-format=human|json] [--max-call-depth=<n>] [script]");
+    let names: Vec<_> = WarningKind::ALL.iter().map(|kind| kind.name()).collect();
+    println!("Warnings: {}", names.join(", "));
+    std::process::exit(64);
 }
 
-fn main() -> RLResult<()> {
-    let args: Vec<String> = env::args().collect();
+fn main() {
+    let mut lox = Lox::new();
+    let mut script = None;
 
-    match args.len() {
-        1 => run_prompt().unwrap_or(()),
-        2 => run_file(&args[1]),
-        _ => {
-            println!("Usage: jlox [script]");
-            std::process::exit(64);
+    for arg in env::args().skip(1) {
+        if let Some(names) = arg.strip_prefix("--allow=") {
+            for name in names.split(',') {
+                match WarningKind::from_name(name) {
+                    Some(kind) => lox.allow(kind),
+                    None => usage(),
+                }
+            }
+        } else if let Some(name) = arg.strip_prefix("--error-format=") {
+            match ErrorFormat::from_name(name) {
+                Some(format) => lox.set_error_format(format),
+                None => usage(),
+            }
+        } else if let Some(depth) = arg.strip_prefix("--max-call-depth=") {
+            match depth.parse() {
+                Ok(depth) => lox.interpreter().set_max_call_depth(depth),
+                Err(_) => usage(),
+            }
+        } else if script.is_none() && !arg.starts_with("--") {
+            script = Some(arg);
+        } else {
+            usage();
         }
-    };
+    }
 
-    Ok(())
+    match script {
+        None => repl::run_prompt(lox).unwrap_or(()),
+        Some(path) => run_file(lox, &path),
+    };
 }
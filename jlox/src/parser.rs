@@ -26,11 +26,33 @@ pub fn parse(tokens: Vec<Token>) -> Result<Vec<Stmt>, Errors> {
     }
 }
 
+/// Parses a single expression, optionally followed by a `;`, taking up all
+/// of `tokens`.
+pub fn parse_expression(tokens: Vec<Token>) -> Result<Expr, Errors> {
+    let mut state = State::new(tokens);
+
+    let expression = state.expression().map_err(|error| {
+        let mut errors = std::mem::take(&mut state.errors);
+        errors.push(error);
+        Errors::Parsing(errors)
+    })?;
+    state.matches(&[TokenType::Semicolon]);
+
+    if state.is_at_end() {
+        Ok(expression)
+    } else {
+        Err(Errors::Parsing(vec![parser_error(
+            state.peek(),
+            "Expect end of expression.",
+        )]))
+    }
+}
+
 pub fn parser_error(token: &Token, message: &str) -> LoxError {
     match token.token_type {
-        TokenType::Eof => error_with_location(token.line, &" at end", message),
+        TokenType::Eof => error_with_location(token.span.clone(), &" at end", message),
         _ => error_with_location(
-            token.line,
+            token.span.clone(),
             &(String::from(" at '") + &token.lexeme + "'"),
             message,
         ),
@@ -40,11 +62,19 @@ pub fn parser_error(token: &Token, message: &str) -> LoxError {
 struct State {
     tokens: Vec<Token>,
     current: usize,
+    /// Errors in a lambda's body other than the last, which fails the
+    /// expression the lambda is in. They are reported by the enclosing
+    /// declaration, ahead of its own errors.
+    errors: Vec<LoxError>,
 }
 
 impl State {
     fn new(tokens: Vec<Token>) -> State {
-        State { tokens, current: 0 }
+        State {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+        }
     }
 
     fn peek(&self) -> &Token {
@@ -59,6 +89,15 @@ impl State {
         &self.tokens[self.current - 1]
     }
 
+    fn check_next(&self, token_type: &TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => {
+                std::mem::discriminant(&token.token_type) == std::mem::discriminant(token_type)
+            }
+            None => false,
+        }
+    }
+
     fn check(&self, token_type: &TokenType) -> bool {
         if self.is_at_end() {
             return false;
@@ -72,7 +111,7 @@ impl State {
             self.current += 1;
         }
 
-        return self.previous();
+        self.previous()
     }
 
     fn matches(&mut self, types: &[TokenType]) -> bool {
@@ -92,6 +131,11 @@ impl State {
         }
     }
 
+    /// Checks for an identifier used as a contextual keyword, like `as`.
+    fn check_word(&self, word: &str) -> bool {
+        self.check(&TokenType::Identifier) && self.peek().lexeme == word
+    }
+
     fn synchronize(&mut self) {
         self.advance();
 
@@ -104,6 +148,7 @@ impl State {
                 TokenType::Class
                 | TokenType::Fun
                 | TokenType::Var
+                | TokenType::Import
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
@@ -119,30 +164,99 @@ impl State {
     }
 
     /* Grammar rules functions */
+    fn interpolation(&mut self) -> Result<Expr, LoxError> {
+        let mut parts = Vec::new();
+
+        loop {
+            let token = self.previous().clone();
+            let (TokenType::Interpolation(text) | TokenType::String(text)) = &token.token_type
+            else {
+                unreachable!()
+            };
+
+            let is_last = matches!(token.token_type, TokenType::String(_));
+            parts.push(Expr::Literal {
+                value: string(text.clone()),
+                token,
+            });
+            if is_last {
+                break;
+            }
+
+            parts.push(self.expression()?);
+
+            // The rest of the string starts with the `}` closing the expression.
+            let continues = self.peek().lexeme.starts_with('}')
+                && self.matches(&[
+                    TokenType::Interpolation(String::new()),
+                    TokenType::String(String::new()),
+                ]);
+            if !continues {
+                return Err(parser_error(
+                    self.peek(),
+                    "Expect '}' after interpolated expression.",
+                ));
+            }
+        }
+
+        Ok(Expr::Interpolation { parts })
+    }
+
     fn primary(&mut self) -> Result<Expr, LoxError> {
         if self.matches(&[TokenType::False]) {
             Ok(Expr::Literal {
+                token: self.previous().clone(),
                 value: boolean(false),
             })
         } else if self.matches(&[TokenType::True]) {
             Ok(Expr::Literal {
+                token: self.previous().clone(),
                 value: boolean(true),
             })
         } else if self.matches(&[TokenType::Nil]) {
-            Ok(Expr::Literal { value: nil() })
+            Ok(Expr::Literal {
+                token: self.previous().clone(),
+                value: nil(),
+            })
         } else if self.matches(&[TokenType::Number(0.0), TokenType::String("".into())]) {
             let obj = match &self.previous().token_type {
                 TokenType::Number(value) => number(*value),
                 TokenType::String(value) => string(value.clone()),
                 _ => unreachable!(),
             };
-            Ok(Expr::Literal { value: obj })
+            Ok(Expr::Literal {
+                token: self.previous().clone(),
+                value: obj,
+            })
+        } else if self.matches(&[TokenType::Interpolation(String::new())]) {
+            self.interpolation()
         } else if self.matches(&[TokenType::LeftParen]) {
+            let opening = self.previous().clone();
             let expr = self.expression()?;
-            self.consume(&TokenType::RightParen, "Expect ')' after expression.")?;
+            let closing = self
+                .consume(&TokenType::RightParen, "Expect ')' after expression.")?
+                .clone();
             Ok(Expr::Grouping {
+                opening,
                 expression: Box::new(expr),
+                closing,
             })
+        } else if self.matches(&[TokenType::Fun]) {
+            let keyword = self.previous().clone();
+            let (params, body) = self.function_body("function").map_err(|mut errors| {
+                let last = errors.pop().expect("a failed body has errors");
+                self.errors.append(&mut errors);
+                last
+            })?;
+            Ok(Expr::Lambda {
+                keyword,
+                params,
+                body,
+            })
+        } else if self.matches(&[TokenType::LeftBracket]) {
+            self.list()
+        } else if self.matches(&[TokenType::LeftBrace]) {
+            self.map()
         } else if self.matches(&[TokenType::Identifier]) {
             Ok(Expr::Variable {
                 name: self.previous().clone(),
@@ -163,6 +277,56 @@ impl State {
         }
     }
 
+    fn list(&mut self) -> Result<Expr, LoxError> {
+        let opening = self.previous().clone();
+        let mut elements = Vec::new();
+        if !self.check(&TokenType::RightBracket) {
+            loop {
+                elements.push(self.expression()?);
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let closing = self
+            .consume(&TokenType::RightBracket, "Expect ']' after list elements.")?
+            .clone();
+
+        Ok(Expr::List {
+            opening,
+            elements,
+            closing,
+        })
+    }
+
+    fn map(&mut self) -> Result<Expr, LoxError> {
+        let opening = self.previous().clone();
+        let mut entries = Vec::new();
+        if !self.check(&TokenType::RightBrace) {
+            loop {
+                let key = self.expression()?;
+                self.consume(&TokenType::Colon, "Expect ':' after map key.")?;
+                let value = self.expression()?;
+                entries.push((key, value));
+
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let closing = self
+            .consume(&TokenType::RightBrace, "Expect '}' after map entries.")?
+            .clone();
+
+        Ok(Expr::Map {
+            opening,
+            entries,
+            closing,
+        })
+    }
+
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, LoxError> {
         let mut arguments = Vec::new();
         if !self.check(&TokenType::RightParen) {
@@ -194,6 +358,16 @@ impl State {
         loop {
             if self.matches(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
+            } else if self.matches(&[TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self
+                    .consume(&TokenType::RightBracket, "Expect ']' after index.")?
+                    .clone();
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                }
             } else if self.matches(&[TokenType::Dot]) {
                 let name = self
                     .consume(&TokenType::Identifier, "Expect property name after '.'.")?
@@ -328,6 +502,18 @@ impl State {
                     name,
                     value: Box::new(value),
                 })
+            } else if let Expr::Index {
+                object,
+                bracket,
+                index,
+            } = expr
+            {
+                Ok(Expr::IndexSet {
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                })
             } else {
                 Err(parser_error(&equals, "Invalid assignment target."))
             }
@@ -350,10 +536,12 @@ impl State {
     }
 
     fn print_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
         let value = self.expression().map_err(|error| vec![error])?;
         self.consume(&TokenType::Semicolon, "Expect ';' after value.")
             .map_err(|error| vec![error])?;
         Ok(Stmt::Print {
+            keyword,
             expression: Box::new(value),
         })
     }
@@ -382,6 +570,7 @@ impl State {
     }
 
     fn if_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LeftParen, "Expect '(' after 'if'.")
             .map_err(|err| vec![err])?;
         let condition = Box::new(self.expression().map_err(|err| vec![err])?);
@@ -396,6 +585,7 @@ impl State {
         };
 
         Ok(Stmt::If {
+            keyword,
             condition,
             then_branch,
             else_branch,
@@ -403,16 +593,23 @@ impl State {
     }
 
     fn while_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LeftParen, "Expect '(' after 'while'.")
             .map_err(|err| vec![err])?;
         let condition = Box::new(self.expression().map_err(|err| vec![err])?);
         self.consume(&TokenType::RightParen, "Expect ')' after condition.")
             .map_err(|err| vec![err])?;
         let body = Box::new(self.statement()?);
-        Ok(Stmt::While { condition, body })
+        Ok(Stmt::While {
+            keyword,
+            condition,
+            body,
+            increment: None,
+        })
     }
 
     fn for_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
         self.consume(&TokenType::LeftParen, "Expect '(' after 'for'.")
             .map_err(|err| vec![err])?;
         let initializer = if self.matches(&[TokenType::Semicolon]) {
@@ -425,6 +622,7 @@ impl State {
 
         let condition = if self.check(&TokenType::Semicolon) {
             Box::new(Expr::Literal {
+                token: self.peek().clone(),
                 value: boolean(true),
             })
         } else {
@@ -441,25 +639,17 @@ impl State {
         self.consume(&TokenType::RightParen, "Expect ')' after condition.")
             .map_err(|err| vec![err])?;
 
-        let mut body = self.statement()?;
-        if let Some(increment) = increment {
-            body = Stmt::Block {
-                statements: vec![
-                    body,
-                    Stmt::Expression {
-                        expression: increment,
-                    },
-                ],
-            }
-        }
-
-        body = Stmt::While {
+        // The increment is kept apart from the body so that `continue` still runs it.
+        let mut body = Stmt::While {
+            keyword: keyword.clone(),
             condition,
-            body: Box::new(body),
+            body: Box::new(self.statement()?),
+            increment,
         };
 
         if let Some(initializer) = initializer {
             body = Stmt::Block {
+                brace: keyword,
                 statements: vec![initializer, body],
             }
         }
@@ -470,7 +660,10 @@ impl State {
     fn return_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
         let keyword = self.previous().clone();
         let value = if self.check(&TokenType::Semicolon) {
-            Expr::Literal { value: nil() }
+            Expr::Literal {
+                token: keyword.clone(),
+                value: nil(),
+            }
         } else {
             self.expression().map_err(|err| vec![err])?
         };
@@ -484,8 +677,86 @@ impl State {
         })
     }
 
+    fn break_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenType::Semicolon, "Expect ';' after 'break'.")
+            .map_err(|err| vec![err])?;
+        Ok(Stmt::Break { keyword })
+    }
+
+    fn continue_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenType::Semicolon, "Expect ';' after 'continue'.")
+            .map_err(|err| vec![err])?;
+        Ok(Stmt::Continue { keyword })
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
+        let value = self.expression().map_err(|err| vec![err])?;
+        self.consume(&TokenType::Semicolon, "Expect ';' after thrown value.")
+            .map_err(|err| vec![err])?;
+
+        Ok(Stmt::Throw {
+            keyword,
+            expression: Box::new(value),
+        })
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenType::LeftBrace, "Expect '{' after 'try'.")
+            .map_err(|err| vec![err])?;
+        let body = self.block()?;
+
+        let catch = if self.matches(&[TokenType::Catch]) {
+            self.consume(&TokenType::LeftParen, "Expect '(' after 'catch'.")
+                .map_err(|err| vec![err])?;
+            let name = self
+                .consume(&TokenType::Identifier, "Expect exception variable name.")
+                .map_err(|err| vec![err])?
+                .clone();
+            self.consume(
+                &TokenType::RightParen,
+                "Expect ')' after exception variable.",
+            )
+            .map_err(|err| vec![err])?;
+            self.consume(&TokenType::LeftBrace, "Expect '{' before catch body.")
+                .map_err(|err| vec![err])?;
+            Some((name, self.block()?))
+        } else {
+            None
+        };
+
+        let finally = if self.matches(&[TokenType::Finally]) {
+            self.consume(&TokenType::LeftBrace, "Expect '{' after 'finally'.")
+                .map_err(|err| vec![err])?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(vec![parser_error(
+                self.peek(),
+                "Expect 'catch' or 'finally' after try block.",
+            )]);
+        }
+
+        Ok(Stmt::Try {
+            keyword,
+            body,
+            catch,
+            finally,
+        })
+    }
+
     fn statement(&mut self) -> Result<Stmt, Vec<LoxError>> {
-        if self.matches(&[TokenType::For]) {
+        if self.matches(&[TokenType::Break]) {
+            self.break_statement()
+        } else if self.matches(&[TokenType::Continue]) {
+            self.continue_statement()
+        } else if self.matches(&[TokenType::For]) {
             self.for_statement()
         } else if self.matches(&[TokenType::If]) {
             self.if_statement()
@@ -493,17 +764,85 @@ impl State {
             self.print_statement()
         } else if self.matches(&[TokenType::Return]) {
             self.return_statement()
+        } else if self.matches(&[TokenType::Throw]) {
+            self.throw_statement()
+        } else if self.matches(&[TokenType::Try]) {
+            self.try_statement()
         } else if self.matches(&[TokenType::While]) {
             self.while_statement()
         } else if self.matches(&[TokenType::LeftBrace]) {
+            let brace = self.previous().clone();
             let statements = self.block()?;
-            Ok(Stmt::Block { statements })
+            Ok(Stmt::Block { brace, statements })
         } else {
             self.expression_statement()
         }
     }
 
+    fn import_declaration(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
+        let path = self
+            .consume(
+                &TokenType::String(String::new()),
+                "Expect module path after 'import'.",
+            )
+            .map_err(|error| vec![error])?
+            .clone();
+
+        if !self.check_word("as") {
+            return Err(vec![parser_error(
+                self.peek(),
+                "Expect 'as' after module path.",
+            )]);
+        }
+        self.advance();
+
+        let name = self
+            .consume(&TokenType::Identifier, "Expect module name after 'as'.")
+            .map_err(|error| vec![error])?
+            .clone();
+
+        self.consume(&TokenType::Semicolon, "Expect ';' after import.")
+            .map_err(|error| vec![error])?;
+
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            name,
+        })
+    }
+
+    fn selective_import_declaration(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
+        let path = self.advance().clone();
+
+        self.consume(&TokenType::Import, "Expect 'import' after module path.")
+            .map_err(|error| vec![error])?;
+
+        let mut names = Vec::new();
+        loop {
+            let name = self
+                .consume(&TokenType::Identifier, "Expect name to import.")
+                .map_err(|error| vec![error])?;
+            names.push(name.clone());
+
+            if !self.matches(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(&TokenType::Semicolon, "Expect ';' after import.")
+            .map_err(|error| vec![error])?;
+
+        Ok(Stmt::FromImport {
+            keyword,
+            path,
+            names,
+        })
+    }
+
     fn var_declaration(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
         let name = self
             .consume(&TokenType::Identifier, "Expect variable name.")
             .map_err(|error| vec![error])?
@@ -512,7 +851,10 @@ impl State {
         let initializer = if self.matches(&[TokenType::Equal]) {
             self.expression().map_err(|error| vec![error])?
         } else {
-            Expr::Literal { value: nil() }
+            Expr::Literal {
+                token: name.clone(),
+                value: nil(),
+            }
         };
 
         self.consume(
@@ -522,17 +864,32 @@ impl State {
         .map_err(|error| vec![error])?;
 
         Ok(Stmt::Var {
+            keyword,
             name,
             initializer: Box::new(initializer),
         })
     }
 
-    fn function(&mut self, kind: &str) -> Result<Stmt, Vec<LoxError>> {
+    /// Parses a function declaration after its `keyword`, which is `None`
+    /// for methods.
+    fn function(&mut self, kind: &str, keyword: Option<Token>) -> Result<Stmt, Vec<LoxError>> {
         let name = self
             .consume(&TokenType::Identifier, &format!("Expect {kind}, name."))
             .map_err(|err| vec![err])?
             .clone();
 
+        let (params, body) = self.function_body(kind)?;
+
+        Ok(Stmt::Function {
+            keyword: keyword.unwrap_or_else(|| name.clone()),
+            name,
+            params,
+            body,
+        })
+    }
+
+    /// Parses the parameter list and body shared by declarations and lambdas.
+    fn function_body(&mut self, kind: &str) -> Result<(Vec<Token>, Vec<Stmt>), Vec<LoxError>> {
         self.consume(
             &TokenType::LeftParen,
             &format!("Expect '(' after {kind} name."),
@@ -573,10 +930,11 @@ impl State {
         .map_err(|error| vec![error])?;
         let body = self.block()?;
 
-        Ok(Stmt::Function { name, params, body })
+        Ok((params, body))
     }
 
     fn class_declaration(&mut self) -> Result<Stmt, Vec<LoxError>> {
+        let keyword = self.previous().clone();
         let name = self
             .consume(&TokenType::Identifier, "Expect class name.")
             .map_err(|err| vec![err])?
@@ -597,13 +955,14 @@ impl State {
 
         let mut methods = Vec::new();
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            methods.push(self.function("method", None)?);
         }
 
         self.consume(&TokenType::RightBrace, "Expect '}' after class body.")
             .map_err(|error| vec![error])?;
 
         Ok(Stmt::Class {
+            keyword,
             name,
             methods,
             superclass,
@@ -613,18 +972,26 @@ impl State {
     fn declaration(&mut self) -> Result<Stmt, Vec<LoxError>> {
         let res = if self.matches(&[TokenType::Class]) {
             self.class_declaration()
-        } else if self.matches(&[TokenType::Fun]) {
-            self.function("function")
+        } else if self.check(&TokenType::Fun) && self.check_next(&TokenType::Identifier) {
+            let keyword = self.advance().clone();
+            self.function("function", Some(keyword))
         } else if self.matches(&[TokenType::Var]) {
             self.var_declaration()
+        } else if self.matches(&[TokenType::Import]) {
+            self.import_declaration()
+        } else if self.check_word("from") && self.check_next(&TokenType::String(String::new())) {
+            self.advance();
+            self.selective_import_declaration()
         } else {
             self.statement()
         };
 
-        if res.is_err() {
+        res.map_err(|mut errors| {
             self.synchronize();
-        }
 
-        res
+            let mut all = std::mem::take(&mut self.errors);
+            all.append(&mut errors);
+            all
+        })
     }
 }
@@ -0,0 +1,259 @@
+use std::{borrow::Cow, env, fs, path::PathBuf};
+
+use jlox::{
+    ast::ExprVisitor,
+    ast_printer::AstPrinter,
+    scanner::{scan_tokens, Keyword, TokenType, KEYWORDS},
+    values::LoxObj,
+    Lox, ReportErrors,
+};
+use rustyline::{
+    completion::Completer,
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::{ValidationContext, ValidationResult, Validator},
+    Context, Editor, Helper, Result as RLResult,
+};
+
+use crate::report_warnings;
+
+/// Whether `source` stops in the middle of a construct: an unclosed brace,
+/// parenthesis or bracket, or an unterminated string.
+fn is_incomplete(source: &str) -> bool {
+    let result = scan_tokens(source.into(), "<repl>".into());
+    if result.is_incomplete() {
+        return true;
+    }
+
+    let mut errors = Vec::new();
+    let mut depth = 0;
+    for token in result.unwrap(&mut errors) {
+        match token.token_type {
+            TokenType::LeftBrace | TokenType::LeftParen | TokenType::LeftBracket => depth += 1,
+            TokenType::RightBrace | TokenType::RightParen | TokenType::RightBracket => depth -= 1,
+            _ => {}
+        }
+
+        // Too many closing delimiters can't be fixed by typing more.
+        if depth < 0 {
+            return false;
+        }
+    }
+
+    depth > 0
+}
+
+/// The file under the home directory that keeps the REPL history.
+const HISTORY_FILE: &str = ".jlox_history";
+
+const RESET: &str = "\x1b[0m";
+const KEYWORD: &str = "\x1b[35m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[33m";
+
+/// Completes, highlights and validates REPL input.
+#[derive(Default)]
+struct LoxHelper {
+    /// The globals as of the last prompt, to complete names and properties.
+    globals: Vec<(String, LoxObj)>,
+}
+
+/// The start of the identifier that ends at `end`.
+fn identifier_start(line: &str, end: usize) -> usize {
+    line[..end]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_'))
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+impl Validator for LoxHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> RLResult<ValidationResult> {
+        let input = ctx.input();
+        if !input.trim_start().starts_with(':') && is_incomplete(input) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Completer for LoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> RLResult<(usize, Vec<String>)> {
+        let start = identifier_start(line, pos);
+        let prefix = &line[start..pos];
+
+        // After a `.`, complete the properties of the global before it.
+        let names: Vec<String> = match line[..start].strip_suffix('.') {
+            Some(receiver) => {
+                let receiver = &receiver[identifier_start(receiver, receiver.len())..];
+                self.globals
+                    .iter()
+                    .find(|(name, _)| name == receiver)
+                    .map(|(_, value)| value.0.borrow().property_names())
+                    .unwrap_or_default()
+            }
+            None => KEYWORDS
+                .iter()
+                .map(|(keyword, _)| keyword.to_string())
+                .chain(self.globals.iter().map(|(name, _)| name.clone()))
+                .collect(),
+        };
+
+        let mut candidates: Vec<_> = names
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        let mut errors = Vec::new();
+        let tokens = scan_tokens(line.into(), "<repl>".into()).unwrap(&mut errors);
+
+        let mut highlighted = String::new();
+        let mut last = 0;
+        for token in tokens {
+            let color = match token.token_type {
+                TokenType::String(_) | TokenType::Interpolation(_) => STRING,
+                TokenType::Number(_) => NUMBER,
+                _ if token.lexeme.keyword().is_some() => KEYWORD,
+                _ => continue,
+            };
+
+            let (start, end) = (token.span.start, token.span.end);
+            highlighted.push_str(&line[last..start]);
+            highlighted.push_str(color);
+            highlighted.push_str(&line[start..end]);
+            highlighted.push_str(RESET);
+            last = end;
+        }
+        highlighted.push_str(&line[last..]);
+
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        true
+    }
+}
+
+impl Helper for LoxHelper {}
+
+pub fn run_prompt(mut lox: Lox) -> RLResult<()> {
+    let mut rl: Editor<LoxHelper, DefaultHistory> = Editor::new()?;
+    rl.set_helper(Some(LoxHelper::default()));
+
+    let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+    if let Some(history) = &history {
+        // There is no history yet on the first run.
+        let _ = rl.load_history(history);
+    }
+
+    let result = read_eval_print(&mut rl, &mut lox);
+
+    if let Some(history) = &history {
+        rl.save_history(history)?;
+    }
+    result
+}
+
+fn read_eval_print(rl: &mut Editor<LoxHelper, DefaultHistory>, lox: &mut Lox) -> RLResult<()> {
+    loop {
+        if let Some(helper) = rl.helper_mut() {
+            helper.globals = lox.globals();
+        }
+
+        let line = match rl.readline("> ") {
+            Ok(line) => line,
+            // Ctrl-C drops the input typed so far, which is the way out of
+            // an unfinished multi-line construct.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        rl.add_history_entry(line.clone())?;
+
+        if let Some(command) = line.trim().strip_prefix(':') {
+            run_command(lox, command);
+            continue;
+        }
+
+        let result = lox.run_line(line);
+        report_warnings(lox);
+        match result {
+            Ok(Some(value)) => println!("{value}"),
+            result => result.report(&lox.renderer()),
+        }
+    }
+}
+
+const HELP: &str = "\
+:load <path>   run a file in the current session
+:env           list the global variables and their values
+:reset         forget every definition and start over
+:ast <expr>    show how an expression is parsed
+:tokens <src>  show the tokens scanned from some source
+:help          show this message";
+
+/// Runs a REPL meta-command, given without its leading `:`.
+fn run_command(lox: &mut Lox, command: &str) {
+    let (name, argument) = command
+        .split_once(char::is_whitespace)
+        .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+
+    match name {
+        "load" => match fs::read_to_string(argument) {
+            Ok(source) => {
+                let result = lox.run_named(argument, source);
+                report_warnings(lox);
+                result.report(&lox.renderer());
+            }
+            Err(error) => eprintln!("Could not read '{argument}': {error}"),
+        },
+
+        "env" => {
+            for (name, value) in lox.globals() {
+                println!("{name} = {value}");
+            }
+        }
+
+        "reset" => lox.reset(),
+
+        "ast" => match lox.parse_expression("<repl>", argument) {
+            Ok(expression) => println!("{}", AstPrinter.visit_expr(&expression)),
+            result => result.report(&lox.renderer()),
+        },
+
+        "tokens" => match lox.scan("<repl>", argument) {
+            Ok(tokens) => {
+                for token in tokens {
+                    println!("{} {:?} '{}'", token.span, token.token_type, token.lexeme);
+                }
+            }
+            result => result.report(&lox.renderer()),
+        },
+
+        "help" => println!("{HELP}"),
+
+        _ => eprintln!("Unknown command ':{name}'. Type :help for a list of commands."),
+    }
+}
@@ -5,10 +5,20 @@ use crate::{
     errors::{error, LoxError},
     interpreter::Interpreter,
     parser::parser_error,
-    scanner::Token,
+    scanner::TokenType,
+    scanner::{Span, Token},
     values::LoxPrimitive,
+    warnings::{warning, Warning, WarningKind},
 };
 
+struct Variable {
+    defined: bool,
+    used: bool,
+    /// Only variables from `var` declarations are reported when unused.
+    check_unused: bool,
+    declared_at: Span,
+}
+
 #[derive(Clone, Copy)]
 enum ClassType {
     None,
@@ -25,10 +35,13 @@ enum FunctionType {
 }
 
 pub struct Resolver<'a> {
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Variable>>,
     interpreter: &'a mut Interpreter,
     current_function: FunctionType,
     current_class: ClassType,
+    loop_depth: usize,
+    errors: Vec<LoxError>,
+    warnings: Vec<Warning>,
 }
 
 impl<'a> Resolver<'a> {
@@ -38,6 +51,9 @@ impl<'a> Resolver<'a> {
             interpreter,
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            loop_depth: 0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
@@ -46,281 +62,481 @@ impl<'a> Resolver<'a> {
     }
 
     fn end_scope(&mut self) {
-        self.scopes.pop();
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+
+        let mut unused: Vec<_> = scope
+            .into_iter()
+            .filter(|(name, variable)| {
+                variable.check_unused && !variable.used && !name.starts_with('_')
+            })
+            .collect();
+        unused.sort_by_key(|(_, variable)| variable.declared_at.start);
+
+        for (name, variable) in unused {
+            self.warnings.push(warning(
+                WarningKind::UnusedVariable,
+                variable.declared_at,
+                &format!("Local variable '{name}' is never read."),
+            ));
+        }
     }
 
-    fn declare(&mut self, name: &Token) -> Result<(), LoxError> {
-        if let Some(scope) = self.scopes.last_mut() {
-            if scope.insert(name.lexeme.clone(), false).is_some() {
-                return Err(parser_error(
-                    name,
-                    "Already a variable with this name in this scope.",
-                ));
-            }
-        }
+    /// Records an error and carries on, so that a single run reports every
+    /// static error in the program.
+    fn error(&mut self, error: LoxError) {
+        self.errors.push(error);
+    }
+
+    fn declare(&mut self, name: &Token) {
+        self.declare_variable(name, false);
+    }
 
-        Ok(())
+    fn declare_variable(&mut self, name: &Token, check_unused: bool) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+
+        if let Some(previous) = scope.get(&name.lexeme) {
+            let error = parser_error(name, "Already a variable with this name in this scope.")
+                .with_label(previous.declared_at.clone(), "variable first declared here");
+            self.error(error);
+        } else {
+            scope.insert(
+                name.lexeme.clone(),
+                Variable {
+                    defined: false,
+                    used: false,
+                    check_unused,
+                    declared_at: name.span.clone(),
+                },
+            );
+        }
     }
 
     fn define(&mut self, name: &Token) {
+        if let Some(variable) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(&name.lexeme))
+        {
+            variable.defined = true;
+        }
+    }
+
+    /// Defines a variable the user never declared, like `this` and `super`.
+    fn define_implicit(&mut self, name: &str, span: &Span) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name.lexeme.clone(), true);
+            scope.insert(
+                name.into(),
+                Variable {
+                    defined: true,
+                    used: false,
+                    check_unused: false,
+                    declared_at: span.clone(),
+                },
+            );
         }
     }
 
-    pub fn resolve(&mut self, stmts: &[Stmt]) -> Result<(), LoxError> {
-        for stmt in stmts.iter() {
-            self.visit_stmt(stmt)?;
+    /// The warnings found by the calls to `resolve` so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn resolve(&mut self, stmts: &[Stmt]) -> Result<(), Vec<LoxError>> {
+        self.resolve_statements(stmts);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
         }
+    }
+
+    fn resolve_statements(&mut self, stmts: &[Stmt]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            self.visit_stmt(stmt);
+
+            let jumps = matches!(
+                stmt,
+                Stmt::Return { .. }
+                    | Stmt::Break { .. }
+                    | Stmt::Continue { .. }
+                    | Stmt::Throw { .. }
+            );
+
+            if let (true, Some(next), Some(last)) = (jumps, stmts.get(i + 1), stmts.last()) {
+                self.warnings.push(warning(
+                    WarningKind::UnreachableCode,
+                    next.span().to(&last.span()),
+                    "Unreachable code.",
+                ));
 
-        Ok(())
+                // Still resolve it, to find any errors inside.
+                for stmt in stmts[i + 1..].iter() {
+                    self.visit_stmt(stmt);
+                }
+                break;
+            }
+        }
     }
 
-    fn resolve_local(&mut self, expr: &Expr, name: &Token) {
-        let index = self
+    /// Resolves a variable to the scope declaring it. `read` tells whether
+    /// its value is used, which an assignment doesn't do.
+    fn resolve_local(&mut self, expr: &Expr, name: &Token, read: bool) {
+        let found = self
             .scopes
-            .iter()
+            .iter_mut()
             .rev()
-            .position(|scope| scope.contains_key(&name.lexeme));
+            .enumerate()
+            .find_map(|(index, scope)| scope.get_mut(&name.lexeme).map(|var| (index, var)));
 
-        if let Some(index) = index {
+        if let Some((index, variable)) = found {
+            variable.used |= read;
             self.interpreter.resolve(expr, index);
         }
     }
 
-    fn resolve_function(
-        &mut self,
-        function: &Stmt,
-        function_type: FunctionType,
-    ) -> Result<(), LoxError> {
-        if let Stmt::Function { params, body, .. } = function {
-            let enclosing = self.current_function;
-            self.current_function = function_type;
-            self.begin_scope();
-            for param in params.iter() {
-                self.declare(param)?;
-                self.define(param);
+    fn resolve_function(&mut self, params: &[Token], body: &[Stmt], function_type: FunctionType) {
+        let enclosing = self.current_function;
+        let enclosing_loop_depth = self.loop_depth;
+        self.current_function = function_type;
+        self.loop_depth = 0;
+
+        for param in params.iter() {
+            let shadowed = self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(&param.lexeme));
+
+            if let Some(shadowed) = shadowed {
+                let warning = warning(
+                    WarningKind::ShadowedParameter,
+                    param.span.clone(),
+                    &format!("Parameter '{}' shadows a local variable.", param.lexeme),
+                )
+                .with_label(
+                    shadowed.declared_at.clone(),
+                    "shadowed variable declared here",
+                );
+                self.warnings.push(warning);
             }
-            self.resolve(body)?;
-            self.end_scope();
-            self.current_function = enclosing;
         }
 
-        Ok(())
+        self.begin_scope();
+        for param in params.iter() {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_statements(body);
+        self.end_scope();
+        self.current_function = enclosing;
+        self.loop_depth = enclosing_loop_depth;
     }
 }
 
-impl<'a> ExprVisitor<Result<(), LoxError>> for Resolver<'a> {
-    fn visit_expr(&mut self, expression: &crate::ast::Expr) -> Result<(), LoxError> {
+impl<'a> ExprVisitor<()> for Resolver<'a> {
+    fn visit_expr(&mut self, expression: &crate::ast::Expr) {
         match expression {
             Expr::Variable { name } => {
-                if let Some(scope) = self.scopes.last() {
-                    if !scope.get(&name.lexeme).unwrap_or(&true) {
-                        return Err(error(
-                            name.line,
-                            "Can't read local variable in its own initializer.",
-                        ));
-                    }
+                let in_initializer = self.scopes.last().is_some_and(|scope| {
+                    scope
+                        .get(&name.lexeme)
+                        .is_some_and(|variable| !variable.defined)
+                });
+
+                if in_initializer {
+                    self.error(error(
+                        name.span.clone(),
+                        "Can't read local variable in its own initializer.",
+                    ));
                 }
 
-                self.resolve_local(expression, name);
-
-                Ok(())
+                self.resolve_local(expression, name, true);
             }
 
             Expr::Assignment { name, value } => {
-                self.visit_expr(value)?;
-                self.resolve_local(expression, name);
-                Ok(())
+                self.visit_expr(value);
+                self.resolve_local(expression, name, false);
             }
 
             Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
-                self.visit_expr(left)?;
-                self.visit_expr(right)
+                self.visit_expr(left);
+                self.visit_expr(right);
             }
 
             Expr::Call {
                 callee, arguments, ..
             } => {
-                self.visit_expr(callee)?;
+                self.visit_expr(callee);
                 for argument in arguments.iter() {
-                    self.visit_expr(argument)?;
+                    self.visit_expr(argument);
                 }
-                Ok(())
             }
 
-            Expr::Grouping { expression }
+            Expr::Grouping { expression, .. }
             | Expr::Unary {
                 right: expression, ..
             } => self.visit_expr(expression),
 
-            Expr::Literal { .. } => Ok(()),
+            Expr::Literal { .. } => {}
+
+            Expr::Lambda { params, body, .. } => {
+                self.resolve_function(params, body, FunctionType::Function)
+            }
+
+            Expr::List { elements, .. }
+            | Expr::Interpolation {
+                parts: elements, ..
+            } => {
+                for element in elements.iter() {
+                    self.visit_expr(element);
+                }
+            }
+
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries.iter() {
+                    self.visit_expr(key);
+                    self.visit_expr(value);
+                }
+            }
+
+            Expr::Index { object, index, .. } => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+            }
+
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.visit_expr(value);
+                self.visit_expr(object);
+                self.visit_expr(index);
+            }
 
             Expr::Get { object, .. } => self.visit_expr(object),
 
             Expr::Set { object, value, .. } => {
-                self.visit_expr(value)?;
-                self.visit_expr(object)
+                self.visit_expr(value);
+                self.visit_expr(object);
             }
 
             Expr::This { keyword } => {
-                if let ClassType::Class = self.current_class {
-                    self.resolve_local(expression, keyword);
-                    Ok(())
-                } else {
-                    Err(parser_error(
+                if let ClassType::None = self.current_class {
+                    self.error(parser_error(
                         keyword,
                         "Can't use 'this' outside of a class.",
-                    ))
-                }
-            }
-
-            Expr::Super { keyword, .. } => {
-                if let ClassType::Subclass = self.current_class {
-                    self.resolve_local(expression, keyword);
-                    Ok(())
-                } else if let ClassType::None = self.current_class {
-                    Err(parser_error(
-                        keyword,
-                        "Can't use 'super' outside of a class.",
-                    ))
+                    ));
                 } else {
-                    Err(parser_error(
-                        keyword,
-                        "Can't use 'super' in a class with no superclass.",
-                    ))
+                    self.resolve_local(expression, keyword, true);
                 }
             }
+
+            Expr::Super { keyword, .. } => match self.current_class {
+                ClassType::Subclass => self.resolve_local(expression, keyword, true),
+                ClassType::None => self.error(parser_error(
+                    keyword,
+                    "Can't use 'super' outside of a class.",
+                )),
+                ClassType::Class => self.error(parser_error(
+                    keyword,
+                    "Can't use 'super' in a class with no superclass.",
+                )),
+            },
         }
     }
 }
 
-impl<'a> StmtVisitor<Result<(), LoxError>> for Resolver<'a> {
-    fn visit_stmt(&mut self, statement: &Stmt) -> Result<(), LoxError> {
+impl<'a> StmtVisitor<()> for Resolver<'a> {
+    fn visit_stmt(&mut self, statement: &Stmt) {
         match statement {
-            Stmt::Block { statements } => {
+            Stmt::Block { statements, .. } => {
                 self.begin_scope();
-                self.resolve(statements)?;
+                self.resolve_statements(statements);
                 self.end_scope();
+            }
 
-                Ok(())
+            Stmt::Var {
+                name, initializer, ..
+            } => {
+                self.declare_variable(name, true);
+                self.visit_expr(initializer);
+                self.define(name);
             }
 
-            Stmt::Var { name, initializer } => {
-                self.declare(name)?;
-                self.visit_expr(initializer)?;
+            Stmt::Import { name, .. } => {
+                self.declare(name);
                 self.define(name);
+            }
 
-                Ok(())
+            Stmt::FromImport { names, .. } => {
+                for name in names {
+                    self.declare(name);
+                    self.define(name);
+                }
             }
 
-            Stmt::Function { name, .. } => {
-                self.declare(name)?;
+            Stmt::Function {
+                name, params, body, ..
+            } => {
+                self.declare(name);
                 self.define(name);
-                self.resolve_function(statement, FunctionType::Function)
+                self.resolve_function(params, body, FunctionType::Function);
             }
 
-            Stmt::Expression { expression } | Stmt::Print { expression } => {
-                self.visit_expr(expression)
+            Stmt::Expression { expression }
+            | Stmt::Print { expression, .. }
+            | Stmt::Throw { expression, .. } => self.visit_expr(expression),
+
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.begin_scope();
+                self.resolve_statements(body);
+                self.end_scope();
+
+                if let Some((name, catch_body)) = catch {
+                    self.begin_scope();
+                    self.declare(name);
+                    self.define(name);
+                    self.resolve_statements(catch_body);
+                    self.end_scope();
+                }
+
+                if let Some(finally) = finally {
+                    self.begin_scope();
+                    self.resolve_statements(finally);
+                    self.end_scope();
+                }
             }
 
             Stmt::Return {
                 keyword,
                 expression,
-            } => {
-                if let FunctionType::None = self.current_function {
-                    Err(parser_error(keyword, "Can't return from top-level code."))
-                } else if let FunctionType::Initializer = self.current_function {
-                    if let Expr::Literal { value } = *expression.clone() {
-                        if let Some(LoxPrimitive::Nil) = value.0.borrow().primitive() {
-                            Ok(())
-                        } else {
-                            Err(parser_error(
-                                keyword,
-                                "Can't return a value from an initializer.",
-                            ))
+            } => match self.current_function {
+                FunctionType::None => {
+                    self.error(parser_error(keyword, "Can't return from top-level code."))
+                }
+
+                FunctionType::Initializer => match expression.as_ref() {
+                    Expr::Literal { value, token }
+                        if matches!(value.0.borrow().primitive(), Some(LoxPrimitive::Nil)) =>
+                    {
+                        // `return nil;` is allowed, but the call still evaluates to the instance.
+                        if let TokenType::Nil = token.token_type {
+                            self.warnings.push(warning(
+                                WarningKind::InitReturn,
+                                keyword.span.to(&token.span),
+                                "An initializer always returns 'this'; this value is ignored.",
+                            ));
                         }
-                    } else {
-                        Err(parser_error(
-                            keyword,
-                            "Can't return a value from an initializer.",
-                        ))
                     }
-                } else {
-                    self.visit_expr(expression)
-                }
-            }
+                    _ => self.error(parser_error(
+                        keyword,
+                        "Can't return a value from an initializer.",
+                    )),
+                },
+
+                _ => self.visit_expr(expression),
+            },
 
             Stmt::If {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
-                self.visit_expr(condition)?;
-                self.visit_stmt(then_branch)?;
+                self.visit_expr(condition);
+                self.visit_stmt(then_branch);
                 if let Some(else_branch) = else_branch {
-                    self.visit_stmt(else_branch)?;
+                    self.visit_stmt(else_branch);
                 }
-                Ok(())
             }
 
-            Stmt::While { condition, body } => {
-                self.visit_expr(condition)?;
-                self.visit_stmt(body)
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                self.visit_expr(condition);
+                self.loop_depth += 1;
+                self.visit_stmt(body);
+                self.loop_depth -= 1;
+                if let Some(increment) = increment {
+                    self.visit_expr(increment);
+                }
             }
 
+            Stmt::Break { keyword } if self.loop_depth == 0 => self.error(parser_error(
+                keyword,
+                "Can't use 'break' outside of a loop.",
+            )),
+
+            Stmt::Continue { keyword } if self.loop_depth == 0 => self.error(parser_error(
+                keyword,
+                "Can't use 'continue' outside of a loop.",
+            )),
+
+            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+
             Stmt::Class {
                 name,
                 methods,
                 superclass,
+                ..
             } => {
                 let enclosing = self.current_class;
                 self.current_class = ClassType::Class;
 
-                self.declare(name)?;
+                self.declare(name);
                 self.define(name);
 
                 if let Some(superclass) = superclass {
                     self.current_class = ClassType::Subclass;
-                    if let Expr::Variable {
-                        name: superclass_name,
-                    } = *superclass.clone()
-                    {
-                        if name.lexeme == superclass_name.lexeme {
-                            return Err(parser_error(
-                                &superclass_name,
+                    match superclass.as_ref() {
+                        Expr::Variable {
+                            name: superclass_name,
+                        } if name.lexeme == superclass_name.lexeme => {
+                            self.error(parser_error(
+                                superclass_name,
                                 "A class can't inherit from itself.",
                             ));
                         }
+                        _ => self.visit_expr(superclass),
                     }
-
-                    self.visit_expr(superclass)?;
                 }
 
                 if superclass.is_some() {
                     self.begin_scope();
-                    if let Some(scope) = self.scopes.last_mut() {
-                        scope.insert("super".into(), true);
-                    }
+                    self.define_implicit("super", &name.span);
                 }
 
                 self.begin_scope();
-                if let Some(scope) = self.scopes.last_mut() {
-                    scope.insert("this".into(), true);
-                }
+                self.define_implicit("this", &name.span);
 
                 for method in methods.iter() {
-                    let declaration = if let Stmt::Function { name, .. } = method {
-                        if name.lexeme == "init" {
+                    if let Stmt::Function {
+                        name, params, body, ..
+                    } = method
+                    {
+                        let declaration = if name.lexeme == "init" {
                             FunctionType::Initializer
                         } else {
                             FunctionType::Method
-                        }
-                    } else {
-                        unreachable!()
-                    };
+                        };
 
-                    self.resolve_function(method, declaration)?;
+                        self.resolve_function(params, body, declaration);
+                    }
                 }
 
                 self.end_scope();
@@ -330,8 +546,6 @@ impl<'a> StmtVisitor<Result<(), LoxError>> for Resolver<'a> {
                 }
 
                 self.current_class = enclosing;
-
-                Ok(())
             }
         }
     }
@@ -2,4 +2,4 @@ mod state;
 mod token;
 
 pub use state::{scan_tokens, ScanResult};
-pub use token::{Token, TokenType};
+pub use token::{Keyword, Span, Token, TokenType, KEYWORDS};
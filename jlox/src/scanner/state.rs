@@ -1,3 +1,8 @@
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
 use crate::errors::{error, LoxError};
 
 use super::token::*;
@@ -5,50 +10,130 @@ use super::token::*;
 pub struct ScanResult {
     tokens: Vec<Token>,
     errors: Vec<LoxError>,
+    incomplete: bool,
+    source_id: u32,
 }
 
 impl ScanResult {
+    /// The id given to the scanned source, which every span from it carries.
+    pub fn source_id(&self) -> u32 {
+        self.source_id
+    }
+
+    /// Whether the source ended in the middle of a token, such as an
+    /// unterminated string, so that more input could still complete it.
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
+
     pub fn unwrap(mut self, errors: &mut Vec<LoxError>) -> Vec<Token> {
         errors.append(&mut self.errors);
         self.tokens
     }
 }
 
-pub fn scan_tokens(source: String) -> ScanResult {
-    let mut state = State::new(source);
+/// Scans `source`, tagging every token's span with the name of its `file`.
+pub fn scan_tokens(source: String, file: Rc<str>) -> ScanResult {
+    let mut state = State::new(source, file);
     let mut errors = Vec::new();
 
+    state.shebang();
     while !state.is_at_end() {
-        state.start = state.current;
+        state.begin_token();
         if let Err(error) = state.scan_token() {
             errors.push(error);
         }
     }
 
+    // A `${` that was never closed swallowed the rest of the source.
+    if let Some((span, _)) = state.interpolations.pop() {
+        errors.push(error(span, "Unterminated string interpolation."));
+        state.incomplete = true;
+    }
+
     state.eof();
 
     ScanResult {
         tokens: state.tokens,
         errors,
+        incomplete: state.incomplete,
+        source_id: state.source_id,
     }
 }
 
+/// The id of the next source to be scanned. Spans that aren't from any
+/// source, like `Span::default()`, use 0.
+static NEXT_SOURCE_ID: AtomicU32 = AtomicU32::new(1);
+
 struct State {
     source: String,
+    file: Rc<str>,
+    source_id: u32,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
-    line: usize,
+    line: u32,
+    /// The column of `current`, counted in chars.
+    column: u32,
+    start_line: u32,
+    start_column: u32,
+    incomplete: bool,
+    /// For each `${` still open, where its string started and how many
+    /// braces have been opened inside it since.
+    interpolations: Vec<(Span, usize)>,
 }
 
 impl State {
-    fn new(source: String) -> State {
+    fn new(source: String, file: Rc<str>) -> State {
         State {
             source,
+            file,
+            source_id: NEXT_SOURCE_ID.fetch_add(1, Ordering::Relaxed),
             tokens: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
+            column: 1,
+            start_line: 1,
+            start_column: 1,
+            incomplete: false,
+            interpolations: Vec::new(),
+        }
+    }
+
+    fn begin_token(&mut self) {
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+    }
+
+    /// Called after consuming a '\n'.
+    fn newline(&mut self) {
+        self.line += 1;
+        self.column = 1;
+    }
+
+    /// The span of the lexeme scanned so far.
+    fn span(&self) -> Span {
+        Span {
+            file: self.file.clone(),
+            source: self.source_id,
+            start: self.start,
+            end: self.current,
+            line: self.start_line,
+            column: self.start_column,
+        }
+    }
+
+    /// The span from `start`, on the current line, to the current position.
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            file: self.file.clone(),
+            source: self.source_id,
+            start,
+            end: self.current,
+            line: self.line,
+            column: self.column - self.source[start..self.current].chars().count() as u32,
         }
     }
 
@@ -61,8 +146,27 @@ impl State {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            '}' => match self.interpolations.last_mut() {
+                // This brace closes an interpolated expression, so the string goes on.
+                Some((_, 0)) => {
+                    self.interpolations.pop();
+                    return self.string();
+                }
+                Some((_, depth)) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace);
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            ':' => self.add_token(TokenType::Colon),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
             '-' => self.add_token(TokenType::Minus),
@@ -112,13 +216,15 @@ impl State {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.matches('*') {
+                    return self.block_comment();
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             }
 
             ' ' | '\r' | '\t' => {}
-            '\n' => self.line += 1,
+            '\n' => self.newline(),
 
             '"' => {
                 return self.string();
@@ -126,11 +232,11 @@ impl State {
 
             other => {
                 if other.is_ascii_digit() {
-                    self.number();
+                    return self.number(other);
                 } else if other.is_alpha() {
                     self.identifier();
                 } else {
-                    return Err(error(self.line, "Unexpected character."));
+                    return Err(error(self.span(), "Unexpected character."));
                 }
             }
         }
@@ -138,41 +244,234 @@ impl State {
         Ok(())
     }
 
+    /// Skips a `#!` line at the very start of the source, so that scripts
+    /// can be run directly. The newline is left for `newline` to count.
+    fn shebang(&mut self) {
+        if self.source.starts_with("#!") {
+            while self.peek() != '\n' && !self.is_at_end() {
+                self.advance();
+            }
+        }
+    }
+
+    /// Skips a block comment whose `/*` was just consumed. Block comments
+    /// nest, so every `/*` inside needs its own `*/`.
+    fn block_comment(&mut self) -> Result<(), LoxError> {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                self.incomplete = true;
+                return Err(error(self.span(), "Unterminated block comment."));
+            }
+
+            match self.advance() {
+                '/' if self.matches('*') => depth += 1,
+                '*' if self.matches('/') => depth -= 1,
+                '\n' => self.newline(),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scans a string literal from its opening `"`, or the rest of one from
+    /// the `}` ending an interpolated expression. Escape sequences are decoded
+    /// into the token's value; its lexeme keeps them as written.
     fn string(&mut self) -> Result<(), LoxError> {
+        let mut value = String::new();
+        // Only the first bad escape is reported, but the string is still
+        // scanned to its end so that the rest of the source lines up.
+        let mut invalid = None;
+
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.advance();
+                self.advance();
+
+                self.add_token(TokenType::Interpolation(value));
+                self.interpolations.push((self.span(), 0));
+                return invalid.map_or(Ok(()), Err);
             }
 
-            self.advance();
+            match self.advance() {
+                '\\' => match self.escape() {
+                    Ok(c) => value.push(c),
+                    Err(error) => {
+                        invalid.get_or_insert(error);
+                    }
+                },
+                '\n' => {
+                    self.newline();
+                    value.push('\n');
+                }
+                c => value.push(c),
+            }
         }
 
         if self.is_at_end() {
-            return Err(error(self.line, "Unterminated string."));
+            self.incomplete = true;
+            return Err(error(self.span(), "Unterminated string."));
         }
 
         self.advance();
 
-        let value = String::from(&self.source[self.start + 1..self.current - 1]);
         self.add_token(TokenType::String(value));
-        Ok(())
+        invalid.map_or(Ok(()), Err)
     }
 
-    fn number(&mut self) {
-        while self.peek().is_ascii_digit() {
+    /// Decodes an escape sequence whose `\` was just consumed.
+    fn escape(&mut self) -> Result<char, LoxError> {
+        let start = self.current - 1;
+        let c = if self.is_at_end() {
+            '\0'
+        } else {
+            self.advance()
+        };
+
+        let decoded = match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '\\' => Some('\\'),
+            '"' => Some('"'),
+            '$' => Some('$'),
+            'u' => return self.unicode_escape(start),
+            _ => None,
+        };
+
+        decoded.ok_or_else(|| error(self.span_from(start), "Invalid escape sequence."))
+    }
+
+    /// Decodes the `{1F600}` of a `\u{1F600}` escape starting at `start`.
+    fn unicode_escape(&mut self, start: usize) -> Result<char, LoxError> {
+        if !self.matches('{') {
+            return Err(error(
+                self.span_from(start),
+                "Expect '{' after '\\u' in a Unicode escape.",
+            ));
+        }
+
+        let digits = self.current;
+        while self.peek().is_ascii_hexdigit() {
             self.advance();
         }
+        let end = self.current;
+
+        if !self.matches('}') {
+            return Err(error(
+                self.span_from(start),
+                "Expect '}' after the digits of a Unicode escape.",
+            ));
+        }
+
+        let hex = &self.source[digits..end];
+        if hex.is_empty() || hex.len() > 6 {
+            return Err(error(
+                self.span_from(start),
+                "A Unicode escape must have between 1 and 6 hex digits.",
+            ));
+        }
+
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| error(self.span_from(start), "Invalid Unicode code point."))
+    }
+
+    /// Scans a number literal whose first digit, `first`, was just consumed.
+    /// Besides decimals like `12.5e-3`, this covers `0x`, `0b` and `0o`
+    /// integers, and `_` may separate the digits of any of them.
+    fn number(&mut self, first: char) -> Result<(), LoxError> {
+        if first == '0' {
+            let radix = match self.peek() {
+                'x' | 'X' => Some((16, "hexadecimal")),
+                'b' | 'B' => Some((2, "binary")),
+                'o' | 'O' => Some((8, "octal")),
+                _ => None,
+            };
+            if let Some((radix, name)) = radix {
+                self.advance();
+                return self.radix_number(radix, name);
+            }
+        }
+
+        self.digits(10);
 
         if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             self.advance();
-            while self.peek().is_ascii_digit() {
+            self.digits(10);
+        }
+
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
                 self.advance();
             }
+            if !self.peek().is_ascii_digit() {
+                return Err(error(self.span(), "Expect digits in the exponent."));
+            }
+            self.digits(10);
         }
 
-        let string = &self.source[self.start..self.current];
-        let value = string.parse().unwrap();
+        let text = &self.source[self.start..self.current];
+        if has_misplaced_separator(text, 10) {
+            return Err(error(self.span(), SEPARATOR_ERROR));
+        }
+
+        match text.replace('_', "").parse() {
+            Ok(value) => {
+                self.add_token(TokenType::Number(value));
+                Ok(())
+            }
+            Err(_) => Err(error(self.span(), "Invalid number.")),
+        }
+    }
+
+    /// Scans the digits of an integer in `radix`, after its prefix.
+    fn radix_number(&mut self, radix: u32, name: &str) -> Result<(), LoxError> {
+        let digits = self.current;
+        if !self.peek().is_digit(radix) {
+            // Take in the rest of the literal so that the error covers it.
+            while self.peek().is_alpha_or_digit() {
+                self.advance();
+            }
+            return Err(error(
+                self.span(),
+                &format!(
+                    "Expect {name} digits after '{}'.",
+                    &self.source[self.start..digits]
+                ),
+            ));
+        }
+        self.digits(radix);
+
+        if self.peek().is_alpha_or_digit() {
+            while self.peek().is_alpha_or_digit() {
+                self.advance();
+            }
+            return Err(error(self.span(), &format!("Invalid {name} number.")));
+        }
+
+        let text = &self.source[digits..self.current];
+        if has_misplaced_separator(text, radix) {
+            return Err(error(self.span(), SEPARATOR_ERROR));
+        }
+
+        let value = text
+            .chars()
+            .filter_map(|c| c.to_digit(radix))
+            .fold(0.0, |value, digit| value * radix as f64 + digit as f64);
         self.add_token(TokenType::Number(value));
+        Ok(())
+    }
+
+    /// Consumes a run of digits in `radix`, which may contain `_` separators.
+    fn digits(&mut self, radix: u32) {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            self.advance();
+        }
     }
 
     fn identifier(&mut self) {
@@ -188,12 +487,13 @@ impl State {
     fn add_token(&mut self, token_type: TokenType) {
         let lexeme = String::from(&self.source[self.start..self.current]);
         self.tokens
-            .push(Token::new(token_type, lexeme, self.line, self.current));
+            .push(Token::new(token_type, lexeme, self.span()));
     }
 
     fn advance(&mut self) -> char {
         let c = self.get_current_char();
         self.current += c.len_utf8();
+        self.column += 1;
         c
     }
 
@@ -208,6 +508,7 @@ impl State {
         }
 
         self.current += current.len_utf8();
+        self.column += 1;
         true
     }
 
@@ -235,13 +536,23 @@ impl State {
     }
 
     pub fn eof(&mut self) {
-        self.tokens.push(Token::new(
-            TokenType::Eof,
-            "".into(),
-            self.line,
-            self.current,
-        ));
+        self.begin_token();
+        self.tokens
+            .push(Token::new(TokenType::Eof, "".into(), self.span()));
+    }
+}
+
+const SEPARATOR_ERROR: &str = "A '_' in a number must be followed by a digit.";
+
+/// Whether a `_` in the number `text` isn't followed by a digit in `radix`.
+fn has_misplaced_separator(text: &str, radix: u32) -> bool {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '_' && !chars.peek().is_some_and(|next| next.is_digit(radix)) {
+            return true;
+        }
     }
+    false
 }
 
 trait IsAlpha {
@@ -258,3 +569,213 @@ impl IsAlpha for char {
         self.is_alpha() || self.is_ascii_digit()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The token types scanned from `source`, without the final `Eof`.
+    fn scan(source: &str) -> Vec<TokenType> {
+        let mut errors = Vec::new();
+        let mut tokens = scan_tokens(source.into(), "<test>".into()).unwrap(&mut errors);
+        assert!(errors.is_empty(), "unexpected errors scanning {source:?}");
+        tokens.pop();
+        tokens.into_iter().map(|token| token.token_type).collect()
+    }
+
+    /// The errors reported scanning `source`, with the text they point at.
+    fn scan_errors(source: &str) -> Vec<(String, String)> {
+        let mut errors = Vec::new();
+        scan_tokens(source.into(), "<test>".into()).unwrap(&mut errors);
+        errors
+            .into_iter()
+            .map(|error| {
+                let text = source[error.span.start..error.span.end].to_string();
+                (error.message, text)
+            })
+            .collect()
+    }
+
+    fn string(value: &str) -> TokenType {
+        TokenType::String(value.into())
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        assert_eq!(scan(r#""a\nb""#), [string("a\nb")]);
+        assert_eq!(scan(r#""\t\r\0""#), [string("\t\r\0")]);
+        assert_eq!(scan(r#""\\ \" \$""#), [string("\\ \" $")]);
+    }
+
+    #[test]
+    fn decodes_unicode_escapes() {
+        assert_eq!(scan(r#""\u{41}\u{e9}""#), [string("Aé")]);
+        assert_eq!(scan(r#""\u{1F600}""#), [string("😀")]);
+        assert_eq!(scan(r#""\u{10FFFF}""#), [string("\u{10FFFF}")]);
+    }
+
+    #[test]
+    fn keeps_escapes_in_the_lexeme() {
+        let mut errors = Vec::new();
+        let tokens = scan_tokens(r#""a\n\u{41}""#.into(), "<test>".into()).unwrap(&mut errors);
+        assert_eq!(tokens[0].lexeme, r#""a\n\u{41}""#);
+    }
+
+    #[test]
+    fn decodes_escapes_around_interpolations() {
+        assert_eq!(
+            scan(r#""\t${x}\n""#),
+            [
+                TokenType::Interpolation("\t".into()),
+                TokenType::Identifier,
+                string("\n"),
+            ]
+        );
+        assert_eq!(scan(r#""\${x}""#), [string("${x}")]);
+    }
+
+    #[test]
+    fn reports_invalid_escapes_at_the_escape() {
+        assert_eq!(
+            scan_errors(r#""a \q b""#),
+            [("Invalid escape sequence.".into(), r"\q".into())]
+        );
+        assert_eq!(
+            scan_errors(r#""\u{110000}""#),
+            [("Invalid Unicode code point.".into(), r"\u{110000}".into())]
+        );
+        assert_eq!(
+            scan_errors(r#""\u{D800}""#),
+            [("Invalid Unicode code point.".into(), r"\u{D800}".into())]
+        );
+        assert_eq!(
+            scan_errors(r#""\u{}""#),
+            [(
+                "A Unicode escape must have between 1 and 6 hex digits.".into(),
+                r"\u{}".into()
+            )]
+        );
+        assert_eq!(
+            scan_errors(r#""\u{1234567}""#),
+            [(
+                "A Unicode escape must have between 1 and 6 hex digits.".into(),
+                r"\u{1234567}".into()
+            )]
+        );
+        assert_eq!(
+            scan_errors(r#""\u41""#),
+            [(
+                "Expect '{' after '\\u' in a Unicode escape.".into(),
+                r"\u".into()
+            )]
+        );
+        assert_eq!(
+            scan_errors(r#""\u{41""#),
+            [(
+                "Expect '}' after the digits of a Unicode escape.".into(),
+                r"\u{41".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_only_the_first_invalid_escape() {
+        let errors = scan_errors(r#""\q \w"; print 1;"#);
+        assert_eq!(errors, [("Invalid escape sequence.".into(), r"\q".into())]);
+    }
+
+    fn number(value: f64) -> TokenType {
+        TokenType::Number(value)
+    }
+
+    #[test]
+    fn scans_decimal_numbers() {
+        assert_eq!(scan("0 42 3.25"), [number(0.0), number(42.0), number(3.25)]);
+        assert_eq!(scan("007"), [number(7.0)]);
+        // A `.` without digits after it is a property access.
+        assert_eq!(
+            scan("1.len"),
+            [number(1.0), TokenType::Dot, TokenType::Identifier]
+        );
+    }
+
+    #[test]
+    fn scans_exponents() {
+        assert_eq!(scan("1e-9"), [number(1e-9)]);
+        assert_eq!(scan("6.02E23"), [number(6.02e23)]);
+        assert_eq!(scan("2e+3 2e3"), [number(2000.0), number(2000.0)]);
+    }
+
+    #[test]
+    fn scans_radix_numbers() {
+        assert_eq!(scan("0xFF 0Xff"), [number(255.0), number(255.0)]);
+        assert_eq!(scan("0b1010 0B1"), [number(10.0), number(1.0)]);
+        assert_eq!(scan("0o17 0O7"), [number(15.0), number(7.0)]);
+    }
+
+    #[test]
+    fn scans_digit_separators() {
+        assert_eq!(scan("1_000_000"), [number(1_000_000.0)]);
+        assert_eq!(scan("1_0.2_5e1_0"), [number(10.25e10)]);
+        assert_eq!(scan("0xFFFF_FFFF"), [number(4_294_967_295.0)]);
+        assert_eq!(scan("0b1_0"), [number(2.0)]);
+    }
+
+    #[test]
+    fn reports_malformed_numbers() {
+        let error = |message: &str, text: &str| vec![(message.to_string(), text.to_string())];
+
+        assert_eq!(
+            scan_errors("0x"),
+            error("Expect hexadecimal digits after '0x'.", "0x")
+        );
+        assert_eq!(
+            scan_errors("0xZZ"),
+            error("Expect hexadecimal digits after '0x'.", "0xZZ")
+        );
+        assert_eq!(
+            scan_errors("0o"),
+            error("Expect octal digits after '0o'.", "0o")
+        );
+        assert_eq!(scan_errors("0b12"), error("Invalid binary number.", "0b12"));
+        assert_eq!(scan_errors("0o78"), error("Invalid octal number.", "0o78"));
+        assert_eq!(
+            scan_errors("1e"),
+            error("Expect digits in the exponent.", "1e")
+        );
+        assert_eq!(
+            scan_errors("1e+"),
+            error("Expect digits in the exponent.", "1e+")
+        );
+    }
+
+    #[test]
+    fn reports_misplaced_separators() {
+        let error = |text: &str| vec![(SEPARATOR_ERROR.to_string(), text.to_string())];
+
+        assert_eq!(scan_errors("1_"), error("1_"));
+        assert_eq!(scan_errors("1__0"), error("1__0"));
+        assert_eq!(scan_errors("1_.5"), error("1_.5"));
+        assert_eq!(scan_errors("1_e5"), error("1_e5"));
+        assert_eq!(scan_errors("0b1_"), error("0b1_"));
+    }
+
+    #[test]
+    fn counts_columns_in_chars() {
+        let mut errors = Vec::new();
+        let source = "\"é\ne\" ü\n  \"\\q\"";
+        let mut tokens = scan_tokens(source.into(), "<test>".into()).unwrap(&mut errors);
+        tokens.pop();
+
+        let columns: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.span.line, t.span.column))
+            .collect();
+        assert_eq!(columns, [(1, 1), (3, 3)]);
+        let columns: Vec<_> = errors
+            .iter()
+            .map(|e| (e.span.line, e.span.column))
+            .collect();
+        assert_eq!(columns, [(2, 4), (3, 4)]);
+    }
+}
@@ -1,20 +1,48 @@
-use std::hash;
+use std::{fmt::Display, hash, rc::Rc};
+
+/// A region of a source file. `start` and `end` are byte offsets, while `line`
+/// and `column` (both starting at 1, columns counted in characters) locate `start`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: Rc<str>,
+    /// Which scan the span comes from. Every scan gets its own id, so spans
+    /// from two sources run under the same file name never compare equal.
+    pub source: u32,
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            end: other.end,
+            ..self.clone()
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
-    pub line: usize,
-    byte: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, line: usize, byte: usize) -> Token {
+    pub fn new(token_type: TokenType, lexeme: String, span: Span) -> Token {
         Token {
             token_type,
             lexeme,
-            line,
-            byte,
+            span,
         }
     }
 }
@@ -26,6 +54,9 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
     Comma,
     Dot,
     Minus,
@@ -47,23 +78,33 @@ pub enum TokenType {
     // Literals.
     Identifier,
     String(String),
+    /// The text of a string literal up to a `${`, or between a `}` closing
+    /// an interpolated expression and the next `${`.
+    Interpolation(String),
     Number(f64),
 
     // Keywords.
     And,
+    Break,
+    Catch,
     Class,
+    Continue,
     Else,
     False,
+    Finally,
     Fun,
     For,
     If,
+    Import,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
 
@@ -77,31 +118,42 @@ impl hash::Hash for TokenType {
     }
 }
 
+/// Every reserved word with the token it scans to.
+pub const KEYWORDS: [(&str, TokenType); 23] = [
+    ("and", TokenType::And),
+    ("break", TokenType::Break),
+    ("catch", TokenType::Catch),
+    ("class", TokenType::Class),
+    ("continue", TokenType::Continue),
+    ("else", TokenType::Else),
+    ("false", TokenType::False),
+    ("finally", TokenType::Finally),
+    ("for", TokenType::For),
+    ("fun", TokenType::Fun),
+    ("if", TokenType::If),
+    ("import", TokenType::Import),
+    ("nil", TokenType::Nil),
+    ("or", TokenType::Or),
+    ("print", TokenType::Print),
+    ("return", TokenType::Return),
+    ("super", TokenType::Super),
+    ("this", TokenType::This),
+    ("throw", TokenType::Throw),
+    ("true", TokenType::True),
+    ("try", TokenType::Try),
+    ("var", TokenType::Var),
+    ("while", TokenType::While),
+];
+
 pub trait Keyword {
     fn keyword(&self) -> Option<TokenType>;
 }
 
 impl Keyword for str {
     fn keyword(&self) -> Option<TokenType> {
-        match self {
-            "and" => Some(TokenType::And),
-            "class" => Some(TokenType::Class),
-            "else" => Some(TokenType::Else),
-            "false" => Some(TokenType::False),
-            "for" => Some(TokenType::For),
-            "fun" => Some(TokenType::Fun),
-            "if" => Some(TokenType::If),
-            "nil" => Some(TokenType::Nil),
-            "or" => Some(TokenType::Or),
-            "print" => Some(TokenType::Print),
-            "return" => Some(TokenType::Return),
-            "super" => Some(TokenType::Super),
-            "this" => Some(TokenType::This),
-            "true" => Some(TokenType::True),
-            "var" => Some(TokenType::Var),
-            "while" => Some(TokenType::While),
-
-            _ => None,
-        }
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == self)
+            .map(|(_, token_type)| token_type.clone())
     }
 }
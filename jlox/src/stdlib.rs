@@ -0,0 +1,95 @@
+use std::io::{self, BufRead};
+
+use chrono::Utc;
+
+use crate::{
+    interpreter::Interpreter,
+    values::{list, string, LoxObj},
+};
+
+/// Registers the native functions every program can use.
+pub fn define_globals(interpreter: &mut Interpreter) {
+    interpreter.define_native("clock", || Utc::now().timestamp_millis() as f64 / 1000.0);
+
+    // strings
+    interpreter.define_native("len", |s: String| s.chars().count() as f64);
+    interpreter.define_native("substr", substr);
+    interpreter.define_native("upper", |s: String| s.to_uppercase());
+    interpreter.define_native("lower", |s: String| s.to_lowercase());
+    interpreter.define_native("trim", |s: String| s.trim().to_string());
+    interpreter.define_native("split", split);
+    interpreter.define_native("indexOf", index_of);
+
+    // numbers
+    interpreter.define_native("floor", f64::floor);
+    interpreter.define_native("sqrt", f64::sqrt);
+    interpreter.define_native("pow", f64::powf);
+    interpreter.define_native("abs", f64::abs);
+    interpreter.define_native("parseNumber", parse_number);
+    interpreter.define_native("toString", |value: LoxObj| value.to_string());
+
+    interpreter.define_native("type", |value: LoxObj| value.0.borrow().type_name());
+
+    // input
+    interpreter.define_native("input", || read_line().unwrap_or_default());
+    interpreter.define_native("readLine", read_line);
+}
+
+fn to_index(n: f64, position: usize) -> Result<usize, String> {
+    if n < 0.0 || n.fract() != 0.0 {
+        Err(format!(
+            "Argument {position} must be a non-negative integer."
+        ))
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Returns the characters of `s` from `start` (inclusive) to `end` (exclusive).
+fn substr(s: String, start: f64, end: f64) -> Result<String, String> {
+    let start = to_index(start, 2)?;
+    let end = to_index(end, 3)?;
+    let length = s.chars().count();
+
+    if start > end || end > length {
+        return Err(format!(
+            "Substring range {start}..{end} out of bounds for length {length}."
+        ));
+    }
+
+    Ok(s.chars().skip(start).take(end - start).collect())
+}
+
+/// Splits `s` on every occurrence of `separator` into a list of strings.
+fn split(s: String, separator: String) -> LoxObj {
+    let parts = if separator.is_empty() {
+        s.chars().map(|c| string(c.into())).collect()
+    } else {
+        s.split(&separator)
+            .map(|part| string(part.into()))
+            .collect()
+    };
+
+    list(parts)
+}
+
+/// Returns the character index of the first occurrence of `pattern`, or -1.
+fn index_of(s: String, pattern: String) -> f64 {
+    s.find(&pattern)
+        .map_or(-1.0, |byte| s[..byte].chars().count() as f64)
+}
+
+fn parse_number(s: String) -> Result<f64, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("Can't parse '{s}' as a number."))
+}
+
+/// Reads a line from stdin without its line terminator, or nil at end of input.
+fn read_line() -> Option<String> {
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+    }
+}
@@ -6,4 +6,7 @@ pub trait LoxCallable: LoxValue {
     fn call(&self, interpreter: &mut Interpreter, args: &[LoxObj]) -> Result<LoxObj, RuntimeError>;
 
     fn arity(&self) -> usize;
+
+    /// How this callable is shown in stack traces.
+    fn name(&self) -> String;
 }
@@ -29,6 +29,10 @@ impl Display for LoxClass {
 }
 
 impl LoxValue for LoxClass {
+    fn type_name(&self) -> &'static str {
+        "class"
+    }
+
     fn callable(&self) -> Option<Box<dyn LoxCallable>> {
         Some(Box::new(self.clone()))
     }
@@ -65,6 +69,13 @@ impl LoxCallable for LoxClass {
             .map(|method| method.arity())
             .unwrap_or(0)
     }
+
+    fn name(&self) -> String {
+        match self.find_method("init") {
+            Some(_) => format!("{}.init", self.name),
+            None => self.name.clone(),
+        }
+    }
 }
 
 pub struct LoxInstance {
@@ -86,6 +97,23 @@ impl Display for LoxInstance {
 }
 
 impl LoxValue for LoxInstance {
+    fn type_name(&self) -> &'static str {
+        "instance"
+    }
+
+    fn property_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.fields.keys().cloned().collect();
+        let mut class = Some(&self.class);
+        while let Some(current) = class {
+            names.extend(current.methods.keys().cloned());
+            class = current.superclass.as_deref();
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
     fn get_property(&self, token: &Token) -> LoxProperty {
         let field = self
             .fields
@@ -0,0 +1,120 @@
+use super::{boolean, nil, number, string, LoxObj, LoxPrimitive};
+
+/// Conversion from a Lox value into a Rust value, used to type the
+/// arguments of native functions and to read globals from Rust.
+pub trait FromLox: Sized {
+    fn from_lox(obj: &LoxObj) -> Option<Self>;
+
+    /// Describes the accepted values, as in "Argument 1 must be a number.".
+    fn expected() -> String;
+}
+
+/// Conversion from a Rust value into a Lox value, used for the return
+/// value of native functions and to set globals from Rust.
+pub trait IntoLox {
+    fn into_lox(self) -> LoxObj;
+}
+
+impl FromLox for f64 {
+    fn from_lox(obj: &LoxObj) -> Option<Self> {
+        match obj.0.borrow().primitive() {
+            Some(LoxPrimitive::Number(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn expected() -> String {
+        "a number".into()
+    }
+}
+
+impl FromLox for bool {
+    fn from_lox(obj: &LoxObj) -> Option<Self> {
+        match obj.0.borrow().primitive() {
+            Some(LoxPrimitive::Bool(b)) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn expected() -> String {
+        "a boolean".into()
+    }
+}
+
+impl FromLox for String {
+    fn from_lox(obj: &LoxObj) -> Option<Self> {
+        match obj.0.borrow().primitive() {
+            Some(LoxPrimitive::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn expected() -> String {
+        "a string".into()
+    }
+}
+
+impl<T: FromLox> FromLox for Option<T> {
+    fn from_lox(obj: &LoxObj) -> Option<Self> {
+        match obj.0.borrow().primitive() {
+            Some(LoxPrimitive::Nil) => Some(None),
+            _ => T::from_lox(obj).map(Some),
+        }
+    }
+
+    fn expected() -> String {
+        format!("{} or nil", T::expected())
+    }
+}
+
+impl FromLox for LoxObj {
+    fn from_lox(obj: &LoxObj) -> Option<Self> {
+        Some(obj.clone())
+    }
+
+    fn expected() -> String {
+        "a value".into()
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> LoxObj {
+        number(self)
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> LoxObj {
+        boolean(self)
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> LoxObj {
+        string(self)
+    }
+}
+
+impl IntoLox for &str {
+    fn into_lox(self) -> LoxObj {
+        string(self.into())
+    }
+}
+
+impl IntoLox for () {
+    fn into_lox(self) -> LoxObj {
+        nil()
+    }
+}
+
+impl<T: IntoLox> IntoLox for Option<T> {
+    fn into_lox(self) -> LoxObj {
+        self.map(IntoLox::into_lox).unwrap_or_else(nil)
+    }
+}
+
+impl IntoLox for LoxObj {
+    fn into_lox(self) -> LoxObj {
+        self
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt::Display;
+
+use crate::scanner::{Span, Token};
+
+use super::{number, string, LoxProperty, LoxValue};
+
+/// The value caught for runtime errors raised by the interpreter itself, such
+/// as "Operands must be numbers.".
+pub struct LoxException {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Display for LoxException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl LoxValue for LoxException {
+    fn type_name(&self) -> &'static str {
+        "exception"
+    }
+
+    fn property_names(&self) -> Vec<String> {
+        ["message", "line", "column"].map(String::from).to_vec()
+    }
+
+    fn get_property(&self, name: &Token) -> LoxProperty {
+        match name.lexeme.as_str() {
+            "message" => LoxProperty::Field(string(self.message.clone())),
+            "line" => LoxProperty::Field(number(self.span.line as f64)),
+            "column" => LoxProperty::Field(number(self.span.column as f64)),
+            _ => LoxProperty::Undef,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt::Display;
+
+use crate::scanner::Token;
+
+use super::{fmt_acyclic, native_method, nil, number, LoxObj, LoxPrimitive, LoxProperty, LoxValue};
+
+pub struct LoxList(pub Vec<LoxObj>);
+
+impl LoxList {
+    fn index(&self, index: &LoxObj, allow_end: bool) -> Result<usize, String> {
+        // The index may be this very list, which is already borrowed.
+        let primitive = index
+            .0
+            .try_borrow()
+            .ok()
+            .and_then(|index| index.primitive());
+        let index = match primitive {
+            Some(LoxPrimitive::Number(n)) if n.fract() == 0.0 => n,
+            _ => return Err("List index must be an integer.".into()),
+        };
+
+        let len = self.0.len() + usize::from(allow_end);
+        if index < 0.0 || index >= len as f64 {
+            Err(format!(
+                "Index {index} out of range for list of length {}.",
+                self.0.len()
+            ))
+        } else {
+            Ok(index as usize)
+        }
+    }
+}
+
+impl Display for LoxList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_acyclic(self, f, "[...]", |f| {
+            write!(f, "[")?;
+            for (i, value) in self.0.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{value}")?;
+            }
+            write!(f, "]")
+        })
+    }
+}
+
+impl LoxValue for LoxList {
+    fn type_name(&self) -> &'static str {
+        "list"
+    }
+
+    fn list_mut(&mut self) -> Option<&mut LoxList> {
+        Some(self)
+    }
+
+    fn property_names(&self) -> Vec<String> {
+        ["len", "push", "pop", "insert", "remove"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn get_property(&self, name: &Token) -> LoxProperty {
+        let method = match name.lexeme.as_str() {
+            "len" => native_method("list.len", 0, |_, this, _| {
+                Ok(number(this.with_list(|list| list.0.len()) as f64))
+            }),
+
+            "push" => native_method("list.push", 1, |_, this, args| {
+                this.with_list(|list| list.0.push(args[0].clone()));
+                Ok(nil())
+            }),
+
+            "pop" => native_method("list.pop", 0, |interpreter, this, _| {
+                this.with_list(|list| list.0.pop())
+                    .ok_or_else(|| interpreter.native_error("Can't pop from an empty list."))
+            }),
+
+            "insert" => native_method("list.insert", 2, |interpreter, this, args| {
+                this.with_list(|list| {
+                    let index = list.index(&args[0], true)?;
+                    list.0.insert(index, args[1].clone());
+                    Ok(nil())
+                })
+                .map_err(|message: String| interpreter.native_error(&message))
+            }),
+
+            "remove" => native_method("list.remove", 1, |interpreter, this, args| {
+                this.with_list(|list| {
+                    let index = list.index(&args[0], false)?;
+                    Ok(list.0.remove(index))
+                })
+                .map_err(|message: String| interpreter.native_error(&message))
+            }),
+
+            _ => return LoxProperty::Undef,
+        };
+
+        LoxProperty::Method(method)
+    }
+
+    fn get_index(&self, index: &LoxObj) -> Result<LoxObj, String> {
+        let index = self.index(index, false)?;
+        Ok(self.0[index].clone())
+    }
+
+    fn set_index(&mut self, index: &LoxObj, value: &LoxObj) -> Result<LoxObj, String> {
+        let index = self.index(index, false)?;
+        self.0[index] = value.clone();
+        Ok(value.clone())
+    }
+}
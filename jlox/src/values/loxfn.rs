@@ -1,31 +1,52 @@
 use std::{cell::RefCell, fmt::Display, rc::Rc};
 
-use crate::{ast::Stmt, interpreter::Environment};
+use crate::{
+    ast::Stmt,
+    interpreter::{Completion, Environment},
+    scanner::Token,
+};
 
-use super::{lox_fn, nil, LoxCallable, LoxObj, LoxValue};
+use super::{nil, LoxCallable, LoxObj, LoxValue};
 
 #[derive(Clone)]
-pub struct LoxFn(pub Box<Stmt>, pub Rc<RefCell<Environment>>, pub bool);
+pub struct LoxFn {
+    /// `None` for anonymous functions.
+    pub name: Option<String>,
+    /// The class declaring this function, for methods.
+    pub class_name: Option<String>,
+    pub params: Rc<Vec<Token>>,
+    pub body: Rc<Vec<Stmt>>,
+    pub closure: Rc<RefCell<Environment>>,
+    /// The globals of the module the function was declared in.
+    pub globals: Rc<RefCell<Environment>>,
+    pub is_initializer: bool,
+}
 
 impl Display for LoxFn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Stmt::Function { name, .. } = *self.0.clone() {
-            write!(f, "<fn {}>", name.lexeme)
-        } else {
-            unreachable!()
+        match &self.name {
+            Some(name) => write!(f, "<fn {name}>"),
+            None => write!(f, "<fn anonymous>"),
         }
     }
 }
 
 impl LoxValue for LoxFn {
+    fn type_name(&self) -> &'static str {
+        "function"
+    }
+
     fn callable(&self) -> Option<Box<dyn LoxCallable>> {
         Some(Box::new(self.clone()))
     }
 
     fn bind(&self, this: LoxObj) -> LoxObj {
-        let closure = Environment::with_enclosing(self.1.clone());
+        let closure = Environment::with_enclosing(self.closure.clone());
         closure.borrow_mut().define("this".into(), this);
-        lox_fn(self.0.clone(), closure, self.2)
+        LoxObj(Rc::new(RefCell::new(LoxFn {
+            closure,
+            ..self.clone()
+        })))
     }
 }
 
@@ -35,31 +56,43 @@ impl LoxCallable for LoxFn {
         interpreter: &mut crate::interpreter::Interpreter,
         args: &[LoxObj],
     ) -> Result<LoxObj, crate::errors::RuntimeError> {
-        if let Stmt::Function { params, body, .. } = *self.0.clone() {
-            let environment = Environment::with_enclosing(self.1.clone());
-            for (param, value) in params.iter().zip(args.iter()) {
-                environment
-                    .borrow_mut()
-                    .define(param.lexeme.clone(), value.clone());
-            }
+        let environment = Environment::with_enclosing(self.closure.clone());
+        for (param, value) in self.params.iter().zip(args.iter()) {
+            environment
+                .borrow_mut()
+                .define(param.lexeme.clone(), value.clone());
+        }
 
-            let res = interpreter.execute_block(&body, environment)?;
+        let globals = interpreter.enter_module(self.globals.clone());
+        let completion = interpreter.execute_block(&self.body, environment);
+        interpreter.enter_module(globals);
 
-            if self.2 {
-                self.1.borrow().get_at(0, "this".into())
-            } else {
-                Ok(res.unwrap_or(nil()))
+        let value = match completion? {
+            Completion::Return { value, .. } => value,
+            // A throw escaping the function keeps unwinding as an error,
+            // which a `try` further up the call stack can still catch.
+            completion => {
+                completion.expect_normal()?;
+                nil()
             }
+        };
+
+        if self.is_initializer {
+            self.closure.borrow().get_at(0, "this".into())
         } else {
-            unreachable!()
+            Ok(value)
         }
     }
 
     fn arity(&self) -> usize {
-        if let Stmt::Function { params, .. } = *self.0.clone() {
-            params.len()
-        } else {
-            unreachable!()
+        self.params.len()
+    }
+
+    fn name(&self) -> String {
+        match (&self.class_name, &self.name) {
+            (Some(class), Some(name)) => format!("{class}.{name}"),
+            (None, Some(name)) => name.clone(),
+            (_, None) => "anonymous".into(),
         }
     }
 }
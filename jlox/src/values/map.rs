@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::scanner::Token;
+
+use super::{
+    boolean, fmt_acyclic, list, native_method, nil, number, LoxObj, LoxProperty, LoxValue,
+};
+
+/// A hash map that remembers insertion order, so that printing a map and
+/// iterating its keys is deterministic.
+#[derive(Default)]
+pub struct LoxMap {
+    indices: HashMap<LoxObj, usize>,
+    entries: Vec<(LoxObj, LoxObj)>,
+}
+
+impl LoxMap {
+    pub fn new(entries: Vec<(LoxObj, LoxObj)>) -> Self {
+        let mut map = Self::default();
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        map
+    }
+
+    pub fn get(&self, key: &LoxObj) -> Option<LoxObj> {
+        self.indices
+            .get(key)
+            .map(|index| self.entries[*index].1.clone())
+    }
+
+    pub fn insert(&mut self, key: LoxObj, value: LoxObj) {
+        if let Some(index) = self.indices.get(&key) {
+            self.entries[*index].1 = value;
+        } else {
+            self.indices.insert(key.clone(), self.entries.len());
+            self.entries.push((key, value));
+        }
+    }
+
+    pub fn remove(&mut self, key: &LoxObj) -> Option<LoxObj> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for (key, _) in self.entries[index..].iter() {
+            if let Some(i) = self.indices.get_mut(key) {
+                *i -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn keys(&self) -> Vec<LoxObj> {
+        self.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn values(&self) -> Vec<LoxObj> {
+        self.entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+impl Display for LoxMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_acyclic(self, f, "{...}", |f| {
+            write!(f, "{{")?;
+            for (i, (key, value)) in self.entries.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{key}: {value}")?;
+            }
+            write!(f, "}}")
+        })
+    }
+}
+
+impl LoxValue for LoxMap {
+    fn type_name(&self) -> &'static str {
+        "map"
+    }
+
+    fn map_mut(&mut self) -> Option<&mut LoxMap> {
+        Some(self)
+    }
+
+    fn property_names(&self) -> Vec<String> {
+        ["len", "keys", "values", "has", "remove"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn get_property(&self, name: &Token) -> LoxProperty {
+        let method = match name.lexeme.as_str() {
+            "len" => native_method("map.len", 0, |_, this, _| {
+                Ok(number(this.with_map(|map| map.len()) as f64))
+            }),
+
+            "keys" => native_method("map.keys", 0, |_, this, _| {
+                Ok(list(this.with_map(|map| map.keys())))
+            }),
+
+            "values" => native_method("map.values", 0, |_, this, _| {
+                Ok(list(this.with_map(|map| map.values())))
+            }),
+
+            "has" => native_method("map.has", 1, |_, this, args| {
+                Ok(boolean(this.with_map(|map| map.get(&args[0]).is_some())))
+            }),
+
+            "remove" => native_method("map.remove", 1, |_, this, args| {
+                Ok(this
+                    .with_map(|map| map.remove(&args[0]))
+                    .unwrap_or_else(nil))
+            }),
+
+            _ => return LoxProperty::Undef,
+        };
+
+        LoxProperty::Method(method)
+    }
+
+    fn get_index(&self, index: &LoxObj) -> Result<LoxObj, String> {
+        self.get(index)
+            .ok_or_else(|| format!("Undefined key '{index}'."))
+    }
+
+    fn set_index(&mut self, index: &LoxObj, value: &LoxObj) -> Result<LoxObj, String> {
+        self.insert(index.clone(), value.clone());
+        Ok(value.clone())
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     cell::RefCell,
     collections::HashMap,
-    fmt::{Debug, Display},
+    fmt::{self, Debug, Display, Formatter},
     hash::{self, Hash},
     ops::{Add, Div, Mul, Neg, Sub},
     rc::Rc,
@@ -9,24 +9,38 @@ use std::{
 
 mod callable;
 mod classes;
+mod convert;
+mod exception;
+mod list;
 mod loxfn;
+mod map;
+mod module;
 mod nativefn;
+mod nativemethod;
 mod primitives;
 
 pub use callable::*;
 pub use classes::*;
+pub use convert::*;
+pub use exception::*;
+pub use list::*;
 pub use loxfn::*;
+pub use map::*;
+pub use module::*;
 pub use nativefn::*;
+pub use nativemethod::*;
 pub use primitives::*;
 
 use crate::{
     ast::Stmt,
     errors::RuntimeError,
     interpreter::{Environment, Interpreter},
-    scanner::Token,
+    scanner::{Span, Token},
 };
 
 pub trait LoxValue: Display {
+    fn type_name(&self) -> &'static str;
+
     fn primitive(&self) -> Option<LoxPrimitive> {
         None
     }
@@ -39,6 +53,14 @@ pub trait LoxValue: Display {
         None
     }
 
+    fn list_mut(&mut self) -> Option<&mut LoxList> {
+        None
+    }
+
+    fn map_mut(&mut self) -> Option<&mut LoxMap> {
+        None
+    }
+
     fn is_truthy(&self) -> bool {
         true
     }
@@ -47,6 +69,11 @@ pub trait LoxValue: Display {
         LoxProperty::Invalid
     }
 
+    /// The names `get_property` knows about, for completion in the REPL.
+    fn property_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     fn set_property(&mut self, _name: &Token, _value: &LoxObj) -> Option<LoxObj> {
         None
     }
@@ -54,6 +81,14 @@ pub trait LoxValue: Display {
     fn bind(&self, _this: LoxObj) -> LoxObj {
         nil()
     }
+
+    fn get_index(&self, _index: &LoxObj) -> Result<LoxObj, String> {
+        Err("Only lists and maps can be indexed.".into())
+    }
+
+    fn set_index(&mut self, _index: &LoxObj, _value: &LoxObj) -> Result<LoxObj, String> {
+        Err("Only lists and maps can be indexed.".into())
+    }
 }
 
 #[derive(Clone)]
@@ -83,6 +118,59 @@ impl LoxObj {
     pub fn class(&self) -> Option<LoxClass> {
         self.0.borrow().class()
     }
+
+    pub fn get_index(&self, index: &LoxObj) -> Result<LoxObj, String> {
+        self.0.borrow().get_index(index)
+    }
+
+    pub fn set_index(&self, index: &LoxObj, value: &LoxObj) -> Result<LoxObj, String> {
+        self.0.borrow_mut().set_index(index, value)
+    }
+
+    /// Runs `f` on the underlying list. Panics if this object isn't a list.
+    pub fn with_list<T>(&self, f: impl FnOnce(&mut LoxList) -> T) -> T {
+        f(self
+            .0
+            .borrow_mut()
+            .list_mut()
+            .expect("object is not a list"))
+    }
+
+    /// Runs `f` on the underlying map. Panics if this object isn't a map.
+    pub fn with_map<T>(&self, f: impl FnOnce(&mut LoxMap) -> T) -> T {
+        f(self.0.borrow_mut().map_mut().expect("object is not a map"))
+    }
+
+    /// The primitive value of this object, if it has one. Objects that are
+    /// currently borrowed (e.g. a map being used as its own key) are never
+    /// primitives.
+    fn try_primitive(&self) -> Option<LoxPrimitive> {
+        self.0.try_borrow().ok().and_then(|value| value.primitive())
+    }
+}
+
+thread_local! {
+    /// The lists and maps that are being printed, innermost last.
+    static PRINTING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Prints a list or map with `body`, or as `cycle` when it is already being
+/// printed further out, i.e. when it contains itself.
+fn fmt_acyclic<T>(
+    value: &T,
+    f: &mut Formatter<'_>,
+    cycle: &str,
+    body: impl FnOnce(&mut Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    let address = value as *const T as usize;
+    if PRINTING.with_borrow(|printing| printing.contains(&address)) {
+        return write!(f, "{cycle}");
+    }
+
+    PRINTING.with_borrow_mut(|printing| printing.push(address));
+    let result = body(f);
+    PRINTING.with_borrow_mut(|printing| printing.pop());
+    result
 }
 
 impl Display for LoxObj {
@@ -93,7 +181,11 @@ impl Display for LoxObj {
 
 impl Hash for LoxObj {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.0.as_ptr().hash(state);
+        if let Some(primitive) = self.try_primitive() {
+            primitive.hash(state);
+        } else {
+            self.0.as_ptr().hash(state);
+        }
     }
 }
 
@@ -104,11 +196,11 @@ impl Debug for LoxObj {
 }
 
 // It's fine... :D
-#[allow(clippy::vtable_address_comparisons)]
+#[allow(ambiguous_wide_pointer_comparisons)]
 impl PartialEq for LoxObj {
     fn eq(&self, other: &Self) -> bool {
-        let p1 = self.0.borrow().primitive();
-        let p2 = other.0.borrow().primitive();
+        let p1 = self.try_primitive();
+        let p2 = other.try_primitive();
 
         if p1.is_some() && p2.is_some() {
             p1 == p2
@@ -194,14 +286,81 @@ pub fn nil() -> LoxObj {
 }
 
 pub fn native_fn(
+    name: String,
+    arity: usize,
+    function: impl Fn(&mut Interpreter, &[LoxObj]) -> Result<LoxObj, RuntimeError> + 'static,
+) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(NativeFn {
+        name,
+        arity,
+        function: Rc::new(function),
+    })))
+}
+
+pub fn native_method(
+    name: &'static str,
     arity: usize,
-    function: fn(&mut Interpreter, &[LoxObj]) -> Result<LoxObj, RuntimeError>,
+    method: impl Fn(&mut Interpreter, LoxObj, &[LoxObj]) -> Result<LoxObj, RuntimeError> + 'static,
+) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(NativeMethod {
+        name,
+        arity,
+        method: Rc::new(method),
+    })))
+}
+
+pub fn list(values: Vec<LoxObj>) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(LoxList(values))))
+}
+
+pub fn map(entries: Vec<(LoxObj, LoxObj)>) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(LoxMap::new(entries))))
+}
+
+pub fn exception(message: String, span: Span) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(LoxException { message, span })))
+}
+
+pub fn module(name: String, globals: Rc<RefCell<Environment>>) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(LoxModule { name, globals })))
+}
+
+pub fn lox_fn(
+    name: Option<String>,
+    params: Vec<Token>,
+    body: Vec<Stmt>,
+    closure: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
+    is_initializer: bool,
 ) -> LoxObj {
-    LoxObj(Rc::new(RefCell::new(NativeFn(arity, function))))
+    LoxObj(Rc::new(RefCell::new(LoxFn {
+        name,
+        class_name: None,
+        params: Rc::new(params),
+        body: Rc::new(body),
+        closure,
+        globals,
+        is_initializer,
+    })))
 }
 
-pub fn lox_fn(stmt: Box<Stmt>, closure: Rc<RefCell<Environment>>, is_initializer: bool) -> LoxObj {
-    LoxObj(Rc::new(RefCell::new(LoxFn(stmt, closure, is_initializer))))
+pub fn lox_method(
+    class_name: &str,
+    name: &Token,
+    params: Vec<Token>,
+    body: Vec<Stmt>,
+    closure: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
+) -> LoxObj {
+    LoxObj(Rc::new(RefCell::new(LoxFn {
+        name: Some(name.lexeme.clone()),
+        class_name: Some(class_name.into()),
+        params: Rc::new(params),
+        body: Rc::new(body),
+        closure,
+        globals,
+        is_initializer: name.lexeme == "init",
+    })))
 }
 
 pub fn lox_class(
@@ -0,0 +1,38 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::{interpreter::Environment, scanner::Token};
+
+use super::{LoxProperty, LoxValue};
+
+/// An imported file. Its properties are the globals the file defined.
+pub struct LoxModule {
+    pub name: String,
+    pub globals: Rc<RefCell<Environment>>,
+}
+
+impl Display for LoxModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+impl LoxValue for LoxModule {
+    fn type_name(&self) -> &'static str {
+        "module"
+    }
+
+    fn property_names(&self) -> Vec<String> {
+        self.globals
+            .borrow()
+            .values()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn get_property(&self, name: &Token) -> LoxProperty {
+        match self.globals.borrow().get_value(&name.lexeme) {
+            Some(value) => LoxProperty::Field(value),
+            None => LoxProperty::Undef,
+        }
+    }
+}
@@ -1,14 +1,17 @@
-use std::fmt::Display;
+use std::{fmt::Display, rc::Rc};
 
 use crate::{errors::RuntimeError, interpreter::Interpreter};
 
-use super::{LoxCallable, LoxObj, LoxValue};
+use super::{FromLox, IntoLox, LoxCallable, LoxObj, LoxValue};
+
+pub type NativeClosure = dyn Fn(&mut Interpreter, &[LoxObj]) -> Result<LoxObj, RuntimeError>;
 
 #[derive(Clone)]
-pub struct NativeFn(
-    pub usize,
-    pub fn(&mut Interpreter, &[LoxObj]) -> Result<LoxObj, RuntimeError>,
-);
+pub struct NativeFn {
+    pub name: String,
+    pub arity: usize,
+    pub function: Rc<NativeClosure>,
+}
 
 impl Display for NativeFn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -17,6 +20,10 @@ impl Display for NativeFn {
 }
 
 impl LoxValue for NativeFn {
+    fn type_name(&self) -> &'static str {
+        "function"
+    }
+
     fn callable(&self) -> Option<Box<dyn LoxCallable>> {
         Some(Box::new(self.clone()))
     }
@@ -24,10 +31,87 @@ impl LoxValue for NativeFn {
 
 impl LoxCallable for NativeFn {
     fn call(&self, interpreter: &mut Interpreter, args: &[LoxObj]) -> Result<LoxObj, RuntimeError> {
-        self.1(interpreter, args)
+        (self.function)(interpreter, args)
     }
 
     fn arity(&self) -> usize {
-        self.0
+        self.arity
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
     }
 }
+
+/// The value returned by a typed native function: either a plain value or a
+/// `Result` whose error message becomes a runtime error at the call site.
+pub trait NativeReturn {
+    fn into_result(self, interpreter: &Interpreter) -> Result<LoxObj, RuntimeError>;
+}
+
+impl<T: IntoLox> NativeReturn for T {
+    fn into_result(self, _interpreter: &Interpreter) -> Result<LoxObj, RuntimeError> {
+        Ok(self.into_lox())
+    }
+}
+
+impl<T: IntoLox> NativeReturn for Result<T, String> {
+    fn into_result(self, interpreter: &Interpreter) -> Result<LoxObj, RuntimeError> {
+        self.map(IntoLox::into_lox)
+            .map_err(|message| interpreter.native_error(&message))
+    }
+}
+
+/// A Rust closure whose parameters and return value are converted to and
+/// from Lox values. `Args` is the tuple of parameter types.
+pub trait IntoNative<Args> {
+    fn arity(&self) -> usize;
+
+    fn invoke(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[LoxObj],
+    ) -> Result<LoxObj, RuntimeError>;
+}
+
+pub fn convert_arg<T: FromLox>(
+    interpreter: &Interpreter,
+    arg: &LoxObj,
+    position: usize,
+) -> Result<T, RuntimeError> {
+    T::from_lox(arg).ok_or_else(|| {
+        interpreter.native_error(&format!("Argument {position} must be {}.", T::expected()))
+    })
+}
+
+macro_rules! impl_into_native {
+    ($arity:expr $(, $arg:ident: $position:expr)*) => {
+        impl<F, R, $($arg),*> IntoNative<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R,
+            R: NativeReturn,
+            $($arg: FromLox,)*
+        {
+            fn arity(&self) -> usize {
+                $arity
+            }
+
+            #[allow(non_snake_case, unused_variables)]
+            fn invoke(
+                &self,
+                interpreter: &mut Interpreter,
+                args: &[LoxObj],
+            ) -> Result<LoxObj, RuntimeError> {
+                $(let $arg = convert_arg::<$arg>(interpreter, &args[$position - 1], $position)?;)*
+                self($($arg),*).into_result(interpreter)
+            }
+        }
+    };
+}
+
+impl_into_native!(0);
+impl_into_native!(1, A: 1);
+impl_into_native!(2, A: 1, B: 2);
+impl_into_native!(3, A: 1, B: 2, C: 3);
+impl_into_native!(4, A: 1, B: 2, C: 3, D: 4);
+impl_into_native!(5, A: 1, B: 2, C: 3, D: 4, E: 5);
@@ -0,0 +1,37 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::{errors::RuntimeError, interpreter::Interpreter};
+
+use super::{native_fn, LoxObj, LoxValue};
+
+pub type NativeMethodClosure =
+    dyn Fn(&mut Interpreter, LoxObj, &[LoxObj]) -> Result<LoxObj, RuntimeError>;
+
+/// A method implemented in Rust for a built-in type. Like `LoxFn`, it only
+/// becomes callable once bound to the object it was looked up on.
+#[derive(Clone)]
+pub struct NativeMethod {
+    /// The type and method name, as in `list.push`.
+    pub name: &'static str,
+    pub arity: usize,
+    pub method: Rc<NativeMethodClosure>,
+}
+
+impl Display for NativeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native method>")
+    }
+}
+
+impl LoxValue for NativeMethod {
+    fn type_name(&self) -> &'static str {
+        "function"
+    }
+
+    fn bind(&self, this: LoxObj) -> LoxObj {
+        let method = self.method.clone();
+        native_fn(self.name.into(), self.arity, move |interpreter, args| {
+            method(interpreter, this.clone(), args)
+        })
+    }
+}
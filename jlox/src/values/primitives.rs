@@ -21,6 +21,14 @@ impl Eq for LoxPrimitive {}
 impl hash::Hash for LoxPrimitive {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         core::mem::discriminant(self).hash(state);
+        match self {
+            // 0.0 and -0.0 are equal, so they must hash the same
+            LoxPrimitive::Number(n) if *n == 0.0 => 0.0f64.to_bits().hash(state),
+            LoxPrimitive::Number(n) => n.to_bits().hash(state),
+            LoxPrimitive::Bool(b) => b.hash(state),
+            LoxPrimitive::String(s) => s.hash(state),
+            LoxPrimitive::Nil => {}
+        }
     }
 }
 
@@ -36,6 +44,15 @@ impl Display for LoxPrimitive {
 }
 
 impl LoxValue for LoxPrimitive {
+    fn type_name(&self) -> &'static str {
+        match self {
+            LoxPrimitive::Number(_) => "number",
+            LoxPrimitive::Bool(_) => "boolean",
+            LoxPrimitive::String(_) => "string",
+            LoxPrimitive::Nil => "nil",
+        }
+    }
+
     fn primitive(&self) -> Option<LoxPrimitive> {
         Some(self.clone())
     }
@@ -0,0 +1,63 @@
+use crate::{diagnostics::Label, scanner::Span};
+
+/// The kinds of warnings the resolver can report. Unlike errors, warnings
+/// never stop a program from running, and each kind can be allowed
+/// (silenced) on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    UnusedVariable,
+    UnreachableCode,
+    ShadowedParameter,
+    InitReturn,
+}
+
+impl WarningKind {
+    pub const ALL: [WarningKind; 4] = [
+        WarningKind::UnusedVariable,
+        WarningKind::UnreachableCode,
+        WarningKind::ShadowedParameter,
+        WarningKind::InitReturn,
+    ];
+
+    /// The name used to refer to this kind on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::UnusedVariable => "unused-variable",
+            WarningKind::UnreachableCode => "unreachable-code",
+            WarningKind::ShadowedParameter => "shadowed-parameter",
+            WarningKind::InitReturn => "init-return",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WarningKind> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub span: Span,
+    pub message: String,
+    pub labels: Vec<Label>,
+}
+
+pub fn warning(kind: WarningKind, span: Span, message: &str) -> Warning {
+    Warning {
+        kind,
+        span,
+        message: message.into(),
+        labels: Vec::new(),
+    }
+}
+
+impl Warning {
+    /// Points at another location that helps explain this warning.
+    pub fn with_label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+}